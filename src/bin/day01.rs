@@ -1,56 +1,165 @@
-use advent_of_code_2023::*;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
+use advent_of_code_2023::{stream::Throughput, *};
 
 pub const PUZZLE: &str = include_str!("../../puzzles/day01.txt");
 
 /// So OOP was a surprisingly *bad* approach for this particular problem.
 /// Not as easy as it looks! They never are.
-fn main() {
-    println!("Part 1: {}", solve(PUZZLE, Part::One));
-    println!("Part 2: {}", solve(PUZZLE, Part::Two));
+///
+/// Give it a file name to stream that file instead of the puzzle, which is
+/// how we benchmark against synthetic inputs of a few gigabytes.
+fn main() -> io::Result<()> {
+    match std::env::args().nth(1) {
+        Some(path) => {
+            for (n, part) in [(1, Part::One), (2, Part::Two)] {
+                let reader = BufReader::new(File::open(&path)?);
+                let (sum, throughput) = solve_stream(reader, part, 1 << 16)?;
+                println!("Part {n}: {sum} ({throughput})");
+            }
+        }
+        None => {
+            println!("Part 1: {}", solve(PUZZLE, Part::One));
+            println!("Part 2: {}", solve(PUZZLE, Part::Two));
+        }
+    }
+    Ok(())
 }
 
 pub fn solve(input: &str, part: Part) -> usize {
-    let words = [
-        "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "zero", "one", "two", "three", "four",
-        "five", "six", "seven", "eight", "nine",
-    ];
-    let words = match part {
-        Part::One => &words[..10],
-        Part::Two => &words[..],
+    let vocabulary = match part {
+        Part::One => Vocabulary::digits(),
+        Part::Two => Vocabulary::english(),
     };
     input
         .lines()
-        .map(|line| {
-            let (_, x) = words
-                .iter()
-                .enumerate()
-                .flat_map(|(i, w)| {
-                    if let Some(p) = line.find(w) {
-                        Some((p, i % 10))
-                    } else {
-                        None
-                    }
-                })
-                .min()
-                .expect("first digit in line");
-            let (_, y) = words
-                .iter()
-                .enumerate()
-                .flat_map(|(i, w)| {
-                    if let Some(p) = line.rfind(w) {
-                        Some((p, i % 10))
-                    } else {
-                        None
+        .map(|line| calibration(line, &vocabulary).expect("first digit in line"))
+        .sum()
+}
+
+/// [`solve`] for input that doesn't fit in memory. Lines are read one at a
+/// time, or `chunk_lines` at a time and calibrated in parallel if that's more
+/// than one.
+#[allow(dead_code)]
+pub fn solve_stream(
+    reader: impl BufRead + Send,
+    part: Part,
+    chunk_lines: usize,
+) -> io::Result<(usize, Throughput)> {
+    let vocabulary = match part {
+        Part::One => Vocabulary::digits(),
+        Part::Two => Vocabulary::english(),
+    };
+    let calibrate = |line: &str| calibration(line, &vocabulary).expect("first digit in line");
+    let mut sum = 0;
+    let throughput = if chunk_lines > 1 {
+        stream::par_for_each_line(reader, chunk_lines, calibrate, |c| sum += c)?
+    } else {
+        stream::for_each_line(reader, calibrate, |c| sum += c)?
+    };
+    Ok((sum, throughput))
+}
+
+/// The first and last digits in a line, as a two-digit number. A line with
+/// only one digit uses it twice.
+pub fn calibration(line: &str, vocabulary: &Vocabulary) -> Option<usize> {
+    let mut digits = vocabulary.scan(line);
+    let first = digits.next()?;
+    let last = digits.last().unwrap_or(first);
+    Some(10 * first.value + last.value)
+}
+
+/// A word found in a line. `position` and `len` are in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub position: usize,
+    pub len: usize,
+    pub value: usize,
+}
+
+/// A table of words and the values they stand for, stored as a trie so that
+/// we can find every word starting at a position in one walk.
+#[derive(Debug)]
+pub struct Vocabulary {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<u8, usize>,
+    value: Option<usize>,
+}
+
+impl Vocabulary {
+    pub fn new<'a>(words: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut nodes = vec![Node::default()];
+        for (word, value) in words {
+            let mut node = 0;
+            for b in word.bytes() {
+                node = match nodes[node].children.get(&b) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[node].children.insert(b, next);
+                        next
                     }
-                })
-                .max()
-                .unwrap_or((x, x));
-            (x, y)
+                };
+            }
+            nodes[node].value = Some(value);
+        }
+        Self { nodes }
+    }
+
+    pub fn digits() -> Self {
+        Self::new(DIGITS.into_iter().zip(0..))
+    }
+
+    pub fn english() -> Self {
+        Self::new(
+            DIGITS
+                .into_iter()
+                .zip(0..)
+                .chain(ENGLISH.into_iter().zip(0..)),
+        )
+    }
+
+    /// Every word in the line, in order of position. This is where the puzzle
+    /// gets you: words can overlap, so `eightwo` is an 8 *and* a 2, and a
+    /// greedy left-to-right tokenizer would miss the 2.
+    pub fn scan<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match> + 'a {
+        let bytes = line.as_bytes();
+        (0..bytes.len()).flat_map(move |position| {
+            let mut matches = vec![];
+            let mut node = 0;
+            for (len, b) in bytes[position..].iter().enumerate() {
+                match self.nodes[node].children.get(b) {
+                    Some(&next) => node = next,
+                    None => break,
+                }
+                if let Some(value) = self.nodes[node].value {
+                    matches.push(Match {
+                        position,
+                        len: len + 1,
+                        value,
+                    });
+                }
+            }
+            matches
         })
-        .map(|(x, y)| 10 * x + y)
-        .sum()
+    }
 }
 
+const DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+const ENGLISH: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
 #[cfg(test)]
 mod puzzle_name {
     use super::*;
@@ -67,4 +176,62 @@ mod puzzle_name {
     fn test2() {
         assert_eq!(solve(SAMPLE2, Part::Two), 281);
     }
+
+    #[test]
+    fn streaming() {
+        for chunk_lines in [1, 2, 1000] {
+            let (sum, throughput) =
+                solve_stream(SAMPLE1.as_bytes(), Part::One, chunk_lines).unwrap();
+            assert_eq!(sum, 142);
+            assert_eq!(throughput.lines, 4);
+            let (sum, _) = solve_stream(SAMPLE2.as_bytes(), Part::Two, chunk_lines).unwrap();
+            assert_eq!(sum, 281);
+        }
+    }
+
+    #[test]
+    fn overlapping() {
+        let english = Vocabulary::english();
+        let values: Vec<_> = english.scan("eightwothree").map(|m| m.value).collect();
+        assert_eq!(values, [8, 2, 3]);
+        assert_eq!(
+            english.scan("oneight").collect::<Vec<_>>(),
+            [
+                Match {
+                    position: 0,
+                    len: 3,
+                    value: 1
+                },
+                Match {
+                    position: 2,
+                    len: 5,
+                    value: 8
+                }
+            ]
+        );
+        assert_eq!(calibration("xtwone", &english), Some(21));
+        assert_eq!(calibration("7pqrst", &english), Some(77));
+        assert_eq!(calibration("nothing", &Vocabulary::digits()), None);
+    }
+
+    #[test]
+    fn custom_vocabulary() {
+        let spanish = Vocabulary::new([
+            ("uno", 1),
+            ("dos", 2),
+            ("tres", 3),
+            ("cuatro", 4),
+            ("cinco", 5),
+            ("seis", 6),
+            ("siete", 7),
+            ("ocho", 8),
+            ("nueve", 9),
+        ]);
+        assert_eq!(calibration("dosiete", &spanish), Some(27));
+        assert_eq!(calibration("onetwo", &spanish), None);
+        // Prefixes of each other are both reported.
+        let roman = Vocabulary::new([("i", 1), ("ii", 2), ("iii", 3)]);
+        let values: Vec<_> = roman.scan("iii").map(|m| m.value).collect();
+        assert_eq!(values, [1, 2, 3, 1, 2, 1]);
+    }
 }
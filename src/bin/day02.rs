@@ -1,13 +1,18 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 
 use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, digit1, space1};
+use nom::character::complete::{alpha1, space1, u32 as number};
+use nom::combinator::all_consuming;
 use nom::multi::separated_list1;
 use nom::sequence::{delimited, separated_pair};
 use nom::{IResult, Parser};
 
 pub const PUZZLE: &str = include_str!("../../puzzles/day02.txt");
 
+/// The bag from part 1.
+pub const BAG: [(&str, u32); 3] = [("red", 12), ("green", 13), ("blue", 14)];
+
 fn main() -> Result<(), Box<dyn Error>> {
     let puzzle = PUZZLE.trim();
     let games = parse_games(puzzle)?;
@@ -17,55 +22,102 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 
 pub fn part1(games: &[Game]) -> Result<u32, Box<dyn Error>> {
-    let mut part1 = 0;
-    'outer: for game in games {
-        for round in game.subsets.iter() {
-            if round.red > 12 || round.green > 13 || round.blue > 14 {
-                continue 'outer;
-            }
-        }
-        part1 += game.id;
-    }
-    Ok(part1)
+    let bag = Cubes::from(BAG);
+    Ok(games
+        .iter()
+        .filter(|game| game.possible(&bag))
+        .map(|game| game.id)
+        .sum())
 }
 
 pub fn part2(games: &[Game]) -> Result<u32, Box<dyn Error>> {
-    let mut part2 = 0;
-    for game in games {
-        let mut red = 0;
-        let mut green = 0;
-        let mut blue = 0;
-        for round in game.subsets.iter() {
-            red = red.max(round.red);
-            blue = blue.max(round.blue);
-            green = green.max(round.green);
-        }
-        part2 += red * green * blue;
-    }
-    Ok(part2)
+    Ok(games
+        .iter()
+        .map(|game| game.power(&["red", "green", "blue"]))
+        .sum())
 }
 
+/// How many cubes of each colour. This is both what the elf shows us in one
+/// draw and what could be in the bag. Colours we haven't heard of count as
+/// zero.
+pub type Cubes<'a> = BTreeMap<&'a str, u32>;
+
 #[derive(Debug)]
-pub struct Game {
-    id: u32,
-    subsets: Vec<Subset>,
+pub struct Game<'a> {
+    pub id: u32,
+    pub subsets: Vec<Cubes<'a>>,
 }
 
-#[derive(Debug)]
-struct Subset {
-    blue: u32,
-    red: u32,
-    green: u32,
+impl<'a> Game<'a> {
+    /// Could every draw have come out of this bag?
+    pub fn possible(&self, bag: &Cubes) -> bool {
+        self.subsets.iter().all(|subset| {
+            subset
+                .iter()
+                .all(|(colour, &count)| count <= bag.get(colour).copied().unwrap_or(0))
+        })
+    }
+
+    /// The fewest cubes of each colour that make the game possible.
+    pub fn minimal_bag(&self) -> Cubes<'a> {
+        let mut bag = Cubes::new();
+        for subset in &self.subsets {
+            for (&colour, &count) in subset {
+                let min = bag.entry(colour).or_default();
+                *min = (*min).max(count);
+            }
+        }
+        bag
+    }
+
+    /// The product of the minimal bag's counts for the given colours. A colour
+    /// that never appears makes the power zero, as it would in the puzzle.
+    pub fn power(&self, colours: &[&str]) -> u32 {
+        let bag = self.minimal_bag();
+        colours
+            .iter()
+            .map(|colour| bag.get(colour).copied().unwrap_or(0))
+            .product()
+    }
+
+    /// For each colour, the index of the first draw that forces its minimum.
+    #[allow(dead_code)]
+    pub fn witnesses(&self) -> BTreeMap<&'a str, usize> {
+        let bag = self.minimal_bag();
+        let mut witnesses = BTreeMap::new();
+        for (i, subset) in self.subsets.iter().enumerate() {
+            for (&colour, &count) in subset {
+                if count == bag[colour] {
+                    witnesses.entry(colour).or_insert(i);
+                }
+            }
+        }
+        witnesses
+    }
+}
+
+/// Every colour seen in any game.
+#[allow(dead_code)]
+pub fn colours<'a>(games: &[Game<'a>]) -> BTreeSet<&'a str> {
+    games
+        .iter()
+        .flat_map(|game| {
+            game.subsets
+                .iter()
+                .flat_map(|subset| subset.keys().copied())
+        })
+        .collect()
 }
 
-pub fn parse_games(input: &'static str) -> Result<Vec<Game>, Box<dyn Error>> {
-    let (_input, games) = separated_list1(tag("\n"), parse_game).parse(input)?;
+pub fn parse_games(input: &str) -> Result<Vec<Game<'_>>, Box<dyn Error>> {
+    let (_input, games) = all_consuming(separated_list1(tag("\n"), parse_game))
+        .parse(input)
+        .map_err(|e| e.to_owned())?;
     Ok(games)
 }
 
-fn parse_game(input: &str) -> IResult<&str, Game> {
-    let (input, id) = delimited(tag("Game "), digit1, tag(": ")).parse(input)?;
-    let id = id.parse().unwrap();
+fn parse_game(input: &str) -> IResult<&str, Game<'_>> {
+    let (input, id) = delimited(tag("Game "), number, tag(": ")).parse(input)?;
     let (input, games) = separated_list1(tag("; "), parse_subset).parse(input)?;
     Ok((input, Game { id, subsets: games }))
 }
@@ -74,24 +126,14 @@ fn parse_game(input: &str) -> IResult<&str, Game> {
 // https://blog.adamchalmers.com/nom-chars/
 // https://developerlife.com/2023/02/20/guide-to-nom-parsing/
 // https://gist.github.com/ponbac/abde10b07a1a96886dd8b1e188eb4374
-#[allow(unused_mut)]
-fn parse_subset(input: &str) -> IResult<&str, Subset> {
-    let mut blue = 0;
-    let mut red = 0;
-    let mut green = 0;
-
+fn parse_subset(input: &str) -> IResult<&str, Cubes<'_>> {
     let (input, draws) =
-        separated_list1(tag(", "), separated_pair(digit1, space1, alpha1)).parse(input)?;
-    for (count, color) in draws {
-        match color {
-            "blue" => blue = count.parse().unwrap(),
-            "red" => red = count.parse().unwrap(),
-            "green" => green = count.parse().unwrap(),
-            _ => unreachable!(),
-        }
+        separated_list1(tag(", "), separated_pair(number, space1, alpha1)).parse(input)?;
+    let mut subset = Cubes::new();
+    for (count, colour) in draws {
+        *subset.entry(colour).or_default() += count;
     }
-
-    Ok((input, Subset { blue, red, green }))
+    Ok((input, subset))
 }
 
 #[cfg(test)]
@@ -115,4 +157,36 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
         let g = parse_games(SAMPLE).unwrap();
         assert_eq!(2286, part2(&g).unwrap())
     }
+
+    #[test]
+    fn queries() {
+        let g = parse_games(SAMPLE).unwrap();
+        assert_eq!(
+            g[0].minimal_bag(),
+            Cubes::from([("red", 4), ("green", 2), ("blue", 6)])
+        );
+        assert_eq!(
+            g[0].witnesses(),
+            BTreeMap::from([("red", 0), ("green", 1), ("blue", 1)])
+        );
+        assert!(!g[2].possible(&Cubes::from(BAG)));
+        assert!(g[2].possible(&g[2].minimal_bag()));
+        assert_eq!(g[2].power(&["red", "blue"]), 120);
+    }
+
+    #[test]
+    fn other_colours() {
+        // Borrowed from a String, not a literal.
+        let input = String::from("Game 7: 2 yellow, 1 red; 5 yellow\nGame 8: 3 purple");
+        let g = parse_games(&input).unwrap();
+        assert_eq!(
+            colours(&g).into_iter().collect::<Vec<_>>(),
+            ["purple", "red", "yellow"]
+        );
+        assert_eq!(g[0].power(&["yellow", "red"]), 5);
+        assert_eq!(g[0].power(&["yellow", "purple"]), 0);
+        assert_eq!(part1(&g).unwrap(), 0);
+        assert!(g[1].possible(&Cubes::from([("purple", 3)])));
+        assert!(parse_games("Game 1: 3 blue; oops").is_err());
+    }
 }
@@ -13,48 +13,132 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 
 pub fn solve(input: &str) -> Result<(u32, u32), Box<dyn Error>> {
-    let mut part1 = 0;
-    let mut part2 = HashMap::new();
-    let grid = text_to_grid(input)?;
-    let re = Regex::new(r"\d+")?;
-    for (i, line) in input.lines().enumerate() {
-        for m in re.find_iter(line) {
-            let r1 = if i > 0 { i - 1 } else { 0 };
-            let r2 = if i < grid.nrows() - 1 { i + 1 } else { i };
-            let c1 = if m.start() > 0 { m.start() - 1 } else { 0 };
-            let c2 = if m.end() == grid.ncols() {
-                m.end() - 1
-            } else {
-                m.end()
-            };
-
-            let adjacent = !(r1..=r2)
-                .cartesian_product(c1..=c2)
-                .all(|(r, c)| grid[(r, c)] == '.' || grid[(r, c)].is_ascii_digit());
-
-            let value = m.as_str().parse::<u32>()?;
-
-            let gears = (r1..=r2)
-                .cartesian_product(c1..=c2)
-                .into_iter()
-                .filter(|&x| grid[x] == '*')
-                .collect::<Vec<_>>();
-            assert!(gears.len() <= 1);
-            if gears.len() == 1 {
-                part2.entry(gears[0]).or_insert(vec![]).push(value);
+    let schematic = Schematic::new(input)?;
+    let part1 = schematic.part_numbers().map(|n| n.value).sum();
+    let gear_ratio = schematic
+        .symbols_touching('*', 2)
+        .map(|s| schematic.product(s))
+        .sum();
+    Ok((part1, gear_ratio))
+}
+
+/// (row, column)
+pub type Position = (usize, usize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: u32,
+    pub row: usize,
+    /// The columns the digits occupy.
+    pub columns: std::ops::Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: char,
+    pub position: Position,
+}
+
+/// The engine schematic as a bipartite graph between numbers and the symbols
+/// they touch (diagonals count). Numbers and symbols are referred to by their
+/// index, in reading order.
+#[derive(Debug)]
+pub struct Schematic {
+    pub numbers: Vec<Number>,
+    pub symbols: Vec<Symbol>,
+    number_symbols: Vec<Vec<usize>>,
+    symbol_numbers: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    pub fn new(input: &str) -> Result<Self, Box<dyn Error>> {
+        let grid = text_to_grid(input)?;
+        let mut symbols = vec![];
+        let mut symbol_at = HashMap::new();
+        for ((r, c), &kind) in grid.indexed_iter() {
+            if kind != '.' && !kind.is_ascii_digit() {
+                symbol_at.insert((r, c), symbols.len());
+                symbols.push(Symbol {
+                    kind,
+                    position: (r, c),
+                });
             }
+        }
 
-            if adjacent {
-                part1 += value;
+        let re = Regex::new(r"\d+")?;
+        let mut numbers = vec![];
+        let mut number_symbols = vec![];
+        let mut symbol_numbers = vec![vec![]; symbols.len()];
+        for (row, line) in input.lines().enumerate() {
+            for m in re.find_iter(line) {
+                let rows = row.saturating_sub(1)..=(row + 1).min(grid.nrows() - 1);
+                let cols = m.start().saturating_sub(1)..=m.end().min(grid.ncols() - 1);
+                let touching: Vec<usize> = rows
+                    .cartesian_product(cols)
+                    .filter_map(|p| symbol_at.get(&p).copied())
+                    .collect();
+                for &s in &touching {
+                    symbol_numbers[s].push(numbers.len());
+                }
+                numbers.push(Number {
+                    value: m.as_str().parse()?,
+                    row,
+                    columns: m.range(),
+                });
+                number_symbols.push(touching);
             }
         }
+        Ok(Self {
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
+        })
+    }
+
+    /// Numbers adjacent to any symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, s)| !s.is_empty())
+            .map(|(n, _)| n)
+    }
+
+    /// Symbols of this kind touching exactly `n` numbers.
+    pub fn symbols_touching(&self, kind: char, n: usize) -> impl Iterator<Item = usize> {
+        (0..self.symbols.len())
+            .filter(move |&s| self.symbols[s].kind == kind && self.symbol_numbers[s].len() == n)
+    }
+
+    pub fn product(&self, symbol: usize) -> u32 {
+        self.values(symbol).product()
+    }
+
+    fn values(&self, symbol: usize) -> impl Iterator<Item = u32> {
+        self.symbol_numbers[symbol]
+            .iter()
+            .map(|&n| self.numbers[n].value)
+    }
+}
+
+/// Queries for exploring a schematic. The puzzle itself only needs the ones
+/// above.
+#[allow(dead_code)]
+impl Schematic {
+    /// The symbols that this number touches. There can be more than one.
+    pub fn symbols_of(&self, number: usize) -> &[usize] {
+        &self.number_symbols[number]
+    }
+
+    /// The numbers that this symbol touches.
+    pub fn numbers_of(&self, symbol: usize) -> &[usize] {
+        &self.symbol_numbers[symbol]
+    }
+
+    pub fn sum(&self, symbol: usize) -> u32 {
+        self.values(symbol).sum()
     }
-    let gear_ratio = part2
-        .into_values()
-        .filter(|v| v.len() == 2)
-        .map(|v| v[0] * v[1])
-        .sum();
-    Ok((part1, gear_ratio))
 }
 
 #[cfg(test)]
@@ -94,4 +178,34 @@ mod day03 {
         assert_eq!(467835, solve(SAMPLE1).unwrap().1);
         assert_eq!(467835, solve(SAMPLE2).unwrap().1);
     }
+
+    #[test]
+    fn queries() {
+        let schematic = Schematic::new(SAMPLE1).unwrap();
+        assert_eq!(schematic.part_numbers().count(), 8);
+        let unattached: Vec<_> = schematic
+            .numbers
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| schematic.symbols_of(i).is_empty())
+            .map(|(_, n)| n.value)
+            .collect();
+        assert_eq!(unattached, [114, 58]);
+        let lonely: Vec<_> = schematic.symbols_touching('*', 1).collect();
+        assert_eq!(lonely.len(), 1);
+        assert_eq!(schematic.symbols[lonely[0]].position, (4, 3));
+        assert_eq!(schematic.sum(lonely[0]), 617);
+        let plus = schematic.symbols_touching('+', 1).next().unwrap();
+        assert_eq!(schematic.product(plus), 592);
+    }
+
+    #[test]
+    fn shared_gears() {
+        // 5 touches both gears, which is two gear ratios, not an error.
+        let input = "2*5*3\n.....";
+        assert_eq!(solve(input).unwrap(), (10, 25));
+        let schematic = Schematic::new(input).unwrap();
+        assert_eq!(schematic.symbols_of(1), [0, 1]);
+        assert_eq!(schematic.numbers_of(1), [1, 2]);
+    }
 }
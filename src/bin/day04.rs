@@ -1,61 +1,229 @@
-use std::collections::HashSet;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fs::File,
+    io::{BufRead, BufReader},
+    str::FromStr,
+};
 
-use itertools::Itertools;
+use advent_of_code_2023::stream::{self, Throughput};
 
 pub const PUZZLE: &str = include_str!("../../puzzles/day04.txt");
 
-fn main() {
-    println!("Part 1: {}", part1(PUZZLE));
-    println!("Part 2: {}", part2(PUZZLE));
+/// Give it a file name to stream that file instead of the puzzle.
+fn main() -> Result<(), Box<dyn Error>> {
+    match std::env::args().nth(1) {
+        Some(path) => {
+            let reader = BufReader::new(File::open(path)?);
+            let ((part1, part2), throughput) = solve_stream(reader, 1 << 16)?;
+            println!("Part 1: {part1}");
+            println!("Part 2: {part2}");
+            println!("{throughput}");
+        }
+        None => {
+            let cards: Scratchcards = PUZZLE.parse()?;
+            println!("Part 1: {}", cards.points()?);
+            println!("Part 2: {}", cards.cascade(Overrun::Clamp)?.total);
+        }
+    }
+    Ok(())
 }
 
 pub fn part1(input: &str) -> usize {
-    let mut points = 0;
-    for line in input.lines() {
-        let [_card, winners, numbers] = line
-            .split(|c| c == ':' || c == '|')
-            .map(str::trim)
-            .collect_array()
-            .unwrap();
-        let winners: HashSet<&str> = HashSet::from_iter(winners.split_ascii_whitespace());
-        let numbers: HashSet<&str> = HashSet::from_iter(numbers.split_ascii_whitespace());
-        let n = winners.intersection(&numbers).count() as u32;
-        if n > 0 {
-            points += 2usize.pow(n - 1);
+    let cards: Scratchcards = input.parse().expect("scratchcards");
+    cards.points().expect("points")
+}
+
+pub fn part2(input: &str) -> usize {
+    let cards: Scratchcards = input.parse().expect("scratchcards");
+    cards.cascade(Overrun::Clamp).expect("cascade").total
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub id: u32,
+    pub winners: Vec<u32>,
+    pub numbers: Vec<u32>,
+}
+
+impl Card {
+    /// How many different numbers we have that are also winners. A number
+    /// we have twice still only counts once. There are only a handful of
+    /// numbers, so a linear search beats hashing.
+    pub fn match_count(&self) -> usize {
+        self.numbers
+            .iter()
+            .enumerate()
+            .filter(|&(i, n)| self.winners.contains(n) && !self.numbers[..i].contains(n))
+            .count()
+    }
+
+    /// `None` if there are too many to count, which takes 65 matches.
+    pub fn points(&self) -> Option<usize> {
+        match self.match_count() {
+            0 => Some(0),
+            n => 1usize.checked_shl((n - 1).try_into().ok()?),
         }
     }
-    points
 }
 
-pub fn part2(input: &str) -> usize {
-    let mut cards = vec![];
-    for line in input.lines() {
-        let tmp: Vec<_> = line.split(|c| c == ':' || c == '|').collect();
-        let w: HashSet<_> = HashSet::from_iter(tmp[1].split_ascii_whitespace());
-        let n: HashSet<_> = HashSet::from_iter(tmp[2].split_ascii_whitespace());
-        let match_count = w.intersection(&n).count();
-        cards.push(Card {
-            match_count,
-            card_count: 1,
-        });
+impl FromStr for Card {
+    type Err = Box<dyn Error>;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let (card, numbers) = line.split_once(':').ok_or("missing `:`")?;
+        let (winners, numbers) = numbers.split_once('|').ok_or("missing `|`")?;
+        let id = card
+            .strip_prefix("Card")
+            .ok_or("missing `Card`")?
+            .trim()
+            .parse()?;
+        let parse = |s: &str| {
+            s.split_ascii_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<u32>, _>>()
+        };
+        Ok(Card {
+            id,
+            winners: parse(winners)?,
+            numbers: parse(numbers)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scratchcards {
+    pub cards: Vec<Card>,
+}
+
+impl FromStr for Scratchcards {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let cards = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| line.parse().map_err(|e| format!("line {}: {e}", i + 1)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { cards })
+    }
+}
+
+/// What to do when a card wins copies of cards past the end of the table.
+/// The puzzle promises this never happens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overrun {
+    /// Ignore the copies that would fall off the end.
+    Clamp,
+    /// Fail.
+    Report,
+}
+
+/// The result of the part 2 cascade: how many copies we end up with of each
+/// card, and where they came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cascade {
+    pub copies: Vec<usize>,
+    /// For each card, the earlier cards (by index) that won copies of it and
+    /// how many copies each one won.
+    pub sources: Vec<Vec<(usize, usize)>>,
+    pub total: usize,
+}
+
+impl Scratchcards {
+    /// Part 1. Like the cascade, overflow is an error.
+    pub fn points(&self) -> Result<usize, Box<dyn Error>> {
+        self.cards.iter().try_fold(0usize, |total, card| {
+            let points = card
+                .points()
+                .ok_or(format!("card {} is worth too many points", card.id))?;
+            Ok(total
+                .checked_add(points)
+                .ok_or("too many points in total")?)
+        })
     }
-    //dbg!(&cards);
-    for i in 0..cards.len() {
-        let Card {
-            match_count,
-            card_count,
-        } = cards[i];
-        for j in i + 1..=i + match_count {
-            cards[j].card_count += card_count;
+
+    /// Part 2. Every copy of a card wins one copy of each of the next
+    /// `match_count` cards. Counts grow exponentially in the worst case, so
+    /// overflow is an error rather than a wrong answer.
+    pub fn cascade(&self, overrun: Overrun) -> Result<Cascade, Box<dyn Error>> {
+        let n = self.cards.len();
+        let mut copies = vec![1usize; n];
+        let mut sources = vec![vec![]; n];
+        for (i, card) in self.cards.iter().enumerate() {
+            let match_count = card.match_count();
+            if overrun == Overrun::Report && i + match_count >= n {
+                Err(format!(
+                    "card {} wins copies of {match_count} cards but only {} follow it",
+                    card.id,
+                    n - i - 1
+                ))?;
+            }
+            for j in i + 1..(i + 1 + match_count).min(n) {
+                copies[j] = copies[j]
+                    .checked_add(copies[i])
+                    .ok_or_else(|| format!("too many copies of card {}", self.cards[j].id))?;
+                sources[j].push((i, copies[i]));
+            }
         }
+        let total = copies
+            .iter()
+            .try_fold(0usize, |total, &c| total.checked_add(c))
+            .ok_or("too many cards in total")?;
+        Ok(Cascade {
+            copies,
+            sources,
+            total,
+        })
     }
-    cards.into_iter().map(|card| card.card_count).sum()
 }
 
-#[derive(Debug)]
-struct Card {
-    match_count: usize,
-    card_count: usize,
+/// Both parts in one pass over input that doesn't fit in memory. Each card
+/// only wins copies of the next few cards, so for part 2 we only need to
+/// remember how many copies are owed to the cards just ahead. This clamps,
+/// like [`Overrun::Clamp`]. With `chunk_lines` more than one, the cards are
+/// parsed in parallel.
+#[allow(dead_code)]
+pub fn solve_stream(
+    reader: impl BufRead + Send,
+    chunk_lines: usize,
+) -> Result<((usize, usize), Throughput), Box<dyn Error>> {
+    let mut points = Some(0usize);
+    let mut cards = Some(0usize);
+    let mut owed: VecDeque<usize> = VecDeque::new();
+    let mut line = 0;
+    let mut malformed = None;
+    let fold = |card: Option<Card>| {
+        line += 1;
+        let Some(card) = card else {
+            malformed.get_or_insert(line);
+            return;
+        };
+        points = points
+            .zip(card.points())
+            .and_then(|(total, p)| total.checked_add(p));
+        let match_count = card.match_count();
+        let copies = owed.pop_front().unwrap_or(0).saturating_add(1);
+        cards = cards.and_then(|c| c.checked_add(copies));
+        if owed.len() < match_count {
+            owed.resize(match_count, 0);
+        }
+        for owed in owed.iter_mut().take(match_count) {
+            *owed = owed.saturating_add(copies);
+        }
+    };
+    let parse = |line: &str| line.parse().ok();
+    let throughput = if chunk_lines > 1 {
+        stream::par_for_each_line(reader, chunk_lines, parse, fold)?
+    } else {
+        stream::for_each_line(reader, parse, fold)?
+    };
+    if let Some(line) = malformed {
+        Err(format!("line {line} is not a scratchcard"))?;
+    }
+    let points = points.ok_or("too many points in total")?;
+    let cards = cards.ok_or("too many cards in total")?;
+    Ok(((points, cards), throughput))
 }
 
 #[cfg(test)]
@@ -78,4 +246,74 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
     fn test2() {
         assert_eq!(30, part2(SAMPLE));
     }
+
+    #[test]
+    fn streaming() {
+        for chunk_lines in [1, 4, 1000] {
+            let (answers, throughput) = solve_stream(SAMPLE.as_bytes(), chunk_lines).unwrap();
+            assert_eq!(answers, (13, 30));
+            assert_eq!(throughput.lines, 6);
+            assert_eq!(throughput.bytes, SAMPLE.len() as u64);
+        }
+        assert!(solve_stream("Card 1: 1 2 | 3\nCard 2: 1 2 3".as_bytes(), 1).is_err());
+    }
+
+    #[test]
+    fn provenance() {
+        let cascade = SAMPLE
+            .parse::<Scratchcards>()
+            .unwrap()
+            .cascade(Overrun::Report)
+            .unwrap();
+        assert_eq!(cascade.copies, [1, 2, 4, 8, 14, 1]);
+        assert_eq!(cascade.sources[0], []);
+        assert_eq!(cascade.sources[4], [(0, 1), (2, 4), (3, 8)]);
+        assert_eq!(cascade.total, 30);
+    }
+
+    #[test]
+    fn overrun() {
+        // Card 2 wins copies of two cards, but there's only one after it.
+        let cards: Scratchcards = "Card 1: 1 | 2\nCard 2: 1 2 | 1 2\nCard 3: 1 | 2"
+            .parse()
+            .unwrap();
+        assert!(cards.cascade(Overrun::Report).is_err());
+        let cascade = cards.cascade(Overrun::Clamp).unwrap();
+        assert_eq!(cascade.copies, [1, 1, 2]);
+        assert_eq!(part2("Card 1: 5 | 5"), 1);
+        assert_eq!(
+            solve_stream("Card 1: 5 | 5".as_bytes(), 1).unwrap().0,
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        // Every card wins copies of the next two, so the copies follow the
+        // Fibonacci numbers.
+        let input = (1..=100)
+            .map(|i| format!("Card {i}: 1 2 | {}", ["1", "2"][..2.min(100 - i)].join(" ")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let cards: Scratchcards = input.parse().unwrap();
+        assert!(cards.cascade(Overrun::Report).is_err());
+        assert!(solve_stream(input.as_bytes(), 1).is_err());
+        assert!("Card x: 1 | 2".parse::<Card>().is_err());
+
+        let card = |matches: usize| {
+            let numbers = (1..=matches).map(|n| n.to_string()).collect::<Vec<_>>();
+            format!("Card 1: {0} | {0}", numbers.join(" "))
+        };
+        assert_eq!(card(64).parse::<Card>().unwrap().points(), Some(1 << 63));
+        assert_eq!(card(65).parse::<Card>().unwrap().points(), None);
+        assert!(card(65).parse::<Scratchcards>().unwrap().points().is_err());
+        assert!(solve_stream(card(65).as_bytes(), 1).is_err());
+        let two = format!("{}\n{}", card(64), card(64).replacen('1', "2", 1));
+        assert!(two.parse::<Scratchcards>().unwrap().points().is_err());
+        assert!(solve_stream(two.as_bytes(), 1).is_err());
+        assert_eq!(
+            "Card 1: 1 2 | 1 1 1".parse::<Card>().unwrap().match_count(),
+            1
+        );
+    }
 }
@@ -1,113 +1,345 @@
+use std::{collections::HashSet, error::Error, ops::Range, str::FromStr};
+
+use advent_of_code_2023::events;
+
 pub const PUZZLE: &str = include_str!("../../puzzles/day05.txt");
 
-fn main() {
-    let puzzle = PUZZLE.trim();
-    let (seeds, layers) = parse(puzzle);
-    println!("Part 1: {}", part1(&seeds, &layers));
-    println!("Part 2: {}", part2(&seeds, &layers).unwrap());
+fn main() -> Result<(), Box<dyn Error>> {
+    let almanac: Almanac = PUZZLE.trim().parse()?;
+    println!("Part 1: {}", part1(&almanac));
+    println!("Part 2: {}", part2(&almanac).ok_or("no seeds")?);
+    Ok(())
 }
 
-pub fn part1(seeds: &[usize], layers: &[Layer]) -> usize {
-    seeds
-        .iter()
-        .map(|&seed| seed_to_location(seed, layers))
-        .min()
-        .unwrap()
+pub fn part1(almanac: &Almanac) -> usize {
+    let f = almanac.function();
+    almanac.seeds.iter().map(|&seed| f.get(seed)).min().unwrap()
 }
 
-pub fn part2(seeds: &[usize], layers: &[Layer]) -> Option<usize> {
-    let goals = seeds
+/// The seeds come in ranges, so we push whole ranges through the composed
+/// function, splitting them wherever it has a breakpoint.
+pub fn part2(almanac: &Almanac) -> Option<usize> {
+    let ranges: Vec<Range<usize>> = almanac
+        .seeds
         .chunks(2)
-        .map(|range| |value| range[0] <= value && value < range[0] + range[1])
-        .collect::<Vec<_>>();
-    for location in 1..100_000_000 {
-        let seed = location_to_seed(location, layers);
-        if goals.iter().any(|f| f(seed)) {
-            //println!("Candidate solution: seed {seed} -> location {}", seed_to_location(seed, layers));
-            return Some(location);
-        }
-    }
-    None
+        .map(|range| range[0]..range[0] + range[1])
+        .collect();
+    let locations = almanac.function().image(&ranges);
+    locations.first().map(|range| range.start)
+}
+
+#[derive(Debug, Clone)]
+pub struct Almanac {
+    pub seeds: Vec<usize>,
+    pub layers: Vec<Layer>,
 }
 
-fn seed_to_location(seed: usize, layers: &[Layer]) -> usize {
-    let mut value = seed;
-    'outer: for layer in layers {
-        for rule in layer {
-            if rule.src <= value && value < rule.src + rule.len {
-                value = rule.dst + (value - rule.src);
-                continue 'outer;
+impl Almanac {
+    /// A layer by its name in the input, like `seed-to-soil`.
+    #[allow(dead_code)]
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name() == name)
+    }
+
+    /// The composition of the layers that lead from one category to another,
+    /// following the names rather than the order of the input. Fails if the
+    /// chain breaks or goes round in a circle without reaching `to`.
+    pub fn between(&self, from: &str, to: &str) -> Result<Piecewise, Box<dyn Error>> {
+        let _phase = events::phase(format!("compose {from} to {to}"));
+        let mut f = Piecewise::identity();
+        let mut category = from;
+        let mut visited = HashSet::new();
+        while category != to {
+            if !visited.insert(category) {
+                return Err(format!("maps loop back to {category} before reaching {to}").into());
             }
+            let layer = self
+                .layers
+                .iter()
+                .find(|layer| layer.source == category)
+                .ok_or(format!("no map from {category}"))?;
+            f = f.then(&layer.function());
+            events::counter(
+                &format!("pieces through {}", layer.destination),
+                f.pieces.len(),
+            );
+            category = &layer.destination;
         }
+        Ok(f)
+    }
+
+    /// Seed to location.
+    pub fn function(&self) -> Piecewise {
+        self.between("seed", "location")
+            .expect("a chain of maps from seed to location")
     }
-    value
 }
 
-fn location_to_seed(location: usize, layers: &[Layer]) -> usize {
-    let mut value = location;
-    'outer: for layer in layers.iter().rev() {
-        for rule in layer {
-            if rule.dst <= value && value < rule.dst + rule.len {
-                value = rule.src + (value - rule.dst);
-                continue 'outer;
+impl FromStr for Almanac {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut lines = input.lines();
+        let seeds = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seeds:"))
+            .ok_or("missing seeds")?
+            .split_ascii_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        let mut layers: Vec<Layer> = Vec::new();
+        for line in lines {
+            match line {
+                "" => continue,
+                _ if line.ends_with("map:") => {
+                    let name = line.trim_end_matches("map:").trim();
+                    let (source, destination) = name
+                        .split_once("-to-")
+                        .ok_or(format!("bad map name {name}"))?;
+                    layers.push(Layer {
+                        source: source.to_owned(),
+                        destination: destination.to_owned(),
+                        rules: vec![],
+                    });
+                }
+                l => layers
+                    .last_mut()
+                    .ok_or("rule before the first map")?
+                    .rules
+                    .push(l.parse()?),
             }
         }
+        Ok(Self { seeds, layers })
     }
-    value
 }
 
-pub fn parse(input: &str) -> (Vec<usize>, Vec<Layer>) {
-    let mut lines = input.lines();
-    let seeds = lines
-        .next()
-        .unwrap()
-        .split_at(7)
-        .1
-        .split_ascii_whitespace()
-        .map(|s| s.parse().unwrap())
-        .collect();
-    let lines = lines.skip(1);
-    let mut layers = Vec::new();
-    for line in lines {
-        match line {
-            "" => continue,
-            _ if line.ends_with("map:") => layers.push(Layer::default()),
-            l => {
-                let last = layers.len() - 1;
-                layers[last].push(Rule::new(l))
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub source: String,
+    pub destination: String,
+    pub rules: Vec<Rule>,
+}
+
+impl Layer {
+    #[allow(dead_code)]
+    pub fn name(&self) -> String {
+        format!("{}-to-{}", self.source, self.destination)
+    }
+
+    /// The layer as a function. Values that no rule covers map to themselves.
+    /// Where rules overlap, the first one wins, so each rule only gets the
+    /// parts of its source that earlier rules haven't claimed.
+    pub fn function(&self) -> Piecewise {
+        let mut claimed: Vec<Range<usize>> = vec![];
+        let mut pieces = vec![];
+        for rule in self.rules.iter().filter(|rule| rule.len > 0) {
+            let src = rule.src..rule.src + rule.len;
+            let mut piece = |range: Range<usize>| {
+                if !range.is_empty() {
+                    pieces.push(Piece {
+                        dst: rule.dst + (range.start - rule.src),
+                        src: range,
+                    });
+                }
+            };
+            let mut next = src.start;
+            for taken in claimed.iter().filter(|taken| taken.end > src.start) {
+                if taken.start >= src.end {
+                    break;
+                }
+                piece(next..taken.start.min(src.end));
+                next = next.max(taken.end);
+            }
+            piece(next..src.end);
+            claimed.push(src);
+            claimed = merge(claimed);
+        }
+        pieces.sort_by_key(|piece| piece.src.start);
+
+        let mut all = vec![];
+        let mut next = 0;
+        for piece in pieces {
+            if next < piece.src.start {
+                all.push(Piece {
+                    src: next..piece.src.start,
+                    dst: next,
+                });
             }
+            next = piece.src.end;
+            all.push(piece);
         }
+        all.push(Piece {
+            src: next..usize::MAX,
+            dst: next,
+        });
+        Piecewise::new(all)
     }
-    (seeds, layers)
 }
 
-type Layer = Vec<Rule>;
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Rule {
-    dst: usize,
-    src: usize,
-    len: usize,
+    pub dst: usize,
+    pub src: usize,
+    pub len: usize,
 }
 
-impl Rule {
-    fn new(line: &str) -> Self {
+impl FromStr for Rule {
+    type Err = Box<dyn Error>;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
         let line: Vec<usize> = line
             .split_ascii_whitespace()
             .map(str::parse)
-            .map(Result::unwrap)
+            .collect::<Result<_, _>>()?;
+        match line[..] {
+            [dst, src, len] => Ok(Self { dst, src, len }),
+            _ => Err(format!("expected three numbers, got {}", line.len()))?,
+        }
+    }
+}
+
+/// `src` maps to `dst..dst + src.len()`, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    pub src: Range<usize>,
+    pub dst: usize,
+}
+
+impl Piece {
+    fn dst(&self) -> Range<usize> {
+        self.dst..self.dst + self.src.len()
+    }
+}
+
+/// A function on `0..usize::MAX` that is a translation on each of a sorted
+/// list of intervals. Each layer is one of these, and so is any composition
+/// of layers, which is what lets us look up a location in O(log n) without
+/// going through every layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piecewise {
+    pieces: Vec<Piece>,
+}
+
+impl Piecewise {
+    pub fn identity() -> Self {
+        Self::new(vec![Piece {
+            src: 0..usize::MAX,
+            dst: 0,
+        }])
+    }
+
+    /// The pieces must be sorted and cover the domain without gaps. Adjacent
+    /// pieces with the same translation are joined.
+    fn new(pieces: Vec<Piece>) -> Self {
+        let mut joined: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces.into_iter().filter(|piece| !piece.src.is_empty()) {
+            match joined.last_mut() {
+                Some(last) if last.dst().end == piece.dst => last.src.end = piece.src.end,
+                _ => joined.push(piece),
+            }
+        }
+        Self { pieces: joined }
+    }
+
+    #[allow(dead_code)]
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    /// Where the translation changes.
+    #[allow(dead_code)]
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> {
+        self.pieces.iter().skip(1).map(|piece| piece.src.start)
+    }
+
+    fn find(&self, x: usize) -> usize {
+        self.pieces.partition_point(|piece| piece.src.end <= x)
+    }
+
+    pub fn get(&self, x: usize) -> usize {
+        let piece = &self.pieces[self.find(x)];
+        piece.dst + (x - piece.src.start)
+    }
+
+    /// `other` after `self`.
+    pub fn then(&self, other: &Piecewise) -> Piecewise {
+        let mut pieces = vec![];
+        for piece in &self.pieces {
+            let image = piece.dst();
+            for next in &other.pieces[other.find(image.start)..] {
+                if next.src.start >= image.end {
+                    break;
+                }
+                let start = image.start.max(next.src.start);
+                let end = image.end.min(next.src.end);
+                let from = piece.src.start + (start - image.start);
+                pieces.push(Piece {
+                    src: from..from + (end - start),
+                    dst: next.dst + (start - next.src.start),
+                });
+            }
+        }
+        Piecewise::new(pieces)
+    }
+
+    /// The image of a set of ranges, sorted and with overlaps joined.
+    pub fn image(&self, ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+        let mut image = vec![];
+        for range in ranges.iter().filter(|range| !range.is_empty()) {
+            for piece in &self.pieces[self.find(range.start)..] {
+                if piece.src.start >= range.end {
+                    break;
+                }
+                let start = range.start.max(piece.src.start);
+                let end = range.end.min(piece.src.end);
+                let dst = piece.dst + (start - piece.src.start);
+                image.push(dst..dst + (end - start));
+            }
+        }
+        merge(image)
+    }
+
+    /// The inverse function, if this is a bijection. Every puzzle input we've
+    /// seen is.
+    #[allow(dead_code)]
+    pub fn inverse(&self) -> Option<Piecewise> {
+        let mut pieces: Vec<Piece> = self
+            .pieces
+            .iter()
+            .map(|piece| Piece {
+                src: piece.dst(),
+                dst: piece.src.start,
+            })
             .collect();
-        Self {
-            dst: line[0],
-            src: line[1],
-            len: line[2],
+        pieces.sort_by_key(|piece| piece.src.start);
+        let mut next = 0;
+        for piece in &pieces {
+            if piece.src.start != next {
+                return None;
+            }
+            next = piece.src.end;
         }
+        (next == usize::MAX).then(|| Piecewise::new(pieces))
     }
 }
 
+/// Sort the ranges and join any that overlap or touch.
+fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
 #[cfg(test)]
 mod day05 {
     use super::*;
+    use advent_of_code_2023::rng::Lcg;
 
     const SAMPLE: &str = "seeds: 79 14 55 13
 
@@ -145,21 +377,201 @@ humidity-to-location map:
 
     #[test]
     fn test1() {
-        let (seeds, layers) = parse(SAMPLE);
-        assert_eq!(35, part1(&seeds, &layers));
+        let almanac: Almanac = SAMPLE.parse().unwrap();
+        assert_eq!(35, part1(&almanac));
     }
 
     #[test]
     fn test2() {
-        let (seeds, layers) = parse(SAMPLE);
-        assert_eq!(Some(46), part2(&seeds, &layers));
+        let almanac: Almanac = SAMPLE.parse().unwrap();
+        assert_eq!(Some(46), part2(&almanac));
+    }
+
+    /// Go through the layers one at a time, scanning every rule.
+    fn seed_to_location(seed: usize, almanac: &Almanac) -> usize {
+        let mut value = seed;
+        'outer: for layer in &almanac.layers {
+            for rule in &layer.rules {
+                if rule.src <= value && value < rule.src + rule.len {
+                    value = rule.dst + (value - rule.src);
+                    continue 'outer;
+                }
+            }
+        }
+        value
+    }
+
+    fn location_to_seed(location: usize, almanac: &Almanac) -> usize {
+        let mut value = location;
+        'outer: for layer in almanac.layers.iter().rev() {
+            for rule in &layer.rules {
+                if rule.dst <= value && value < rule.dst + rule.len {
+                    value = rule.src + (value - rule.dst);
+                    continue 'outer;
+                }
+            }
+        }
+        value
+    }
+
+    /// The old part 2: try every location in order until one comes from a
+    /// seed we have. Only good for small almanacs.
+    fn brute_force(almanac: &Almanac, limit: usize) -> Option<usize> {
+        let goals = almanac
+            .seeds
+            .chunks(2)
+            .map(|range| |value| range[0] <= value && value < range[0] + range[1])
+            .collect::<Vec<_>>();
+        (0..limit).find(|&location| {
+            let seed = location_to_seed(location, almanac);
+            goals.iter().any(|f| f(seed))
+        })
+    }
+
+    #[test]
+    fn against_brute_force() {
+        let almanac: Almanac = SAMPLE.parse().unwrap();
+        assert_eq!(part2(&almanac), brute_force(&almanac, 100));
+
+        // Random almanacs where each layer shuffles blocks of 0..64, so that
+        // location_to_seed really is the inverse. Values from 64 up aren't
+        // covered by any rule.
+        let mut rng = Lcg::new(2023);
+        let mut random = |n: usize| rng.below(n);
+        for _ in 0..200 {
+            let layers: Vec<Layer> = (0..4)
+                .map(|i| {
+                    let mut cuts: Vec<usize> = (0..4).map(|_| random(64)).collect();
+                    cuts.extend([0, 64]);
+                    cuts.sort();
+                    cuts.dedup();
+                    let mut blocks: Vec<_> = cuts.windows(2).map(|w| (w[0], w[1] - w[0])).collect();
+                    for i in (1..blocks.len()).rev() {
+                        blocks.swap(i, random(i + 1));
+                    }
+                    let mut dst = 0;
+                    let mut layer = vec![];
+                    for (src, len) in blocks {
+                        layer.push(Rule { dst, src, len });
+                        dst += len;
+                    }
+                    Layer {
+                        source: ["seed", "a", "b", "c"][i].to_owned(),
+                        destination: ["a", "b", "c", "location"][i].to_owned(),
+                        rules: layer,
+                    }
+                })
+                .collect();
+            let mut seeds = vec![];
+            for _ in 0..4 {
+                seeds.push(random(76));
+                seeds.push(1 + random(4));
+            }
+            let almanac = Almanac { seeds, layers };
+            assert_eq!(part2(&almanac), brute_force(&almanac, 80), "{almanac:?}");
+            let f = almanac.function();
+            let g = f.inverse().unwrap();
+            for i in 0..80 {
+                assert_eq!(f.get(i), seed_to_location(i, &almanac));
+                assert_eq!(g.get(i), location_to_seed(i, &almanac));
+            }
+        }
+    }
+
+    /// Every seed, one at a time. Unlike [`brute_force`], this doesn't need
+    /// the almanac to be invertible.
+    fn every_seed(almanac: &Almanac) -> Option<usize> {
+        almanac
+            .seeds
+            .chunks(2)
+            .flat_map(|range| range[0]..range[0] + range[1])
+            .map(|seed| seed_to_location(seed, almanac))
+            .min()
+    }
+
+    #[test]
+    fn overlapping_rules() {
+        let almanac: Almanac = "seeds: 0 15\n\nseed-to-location map:\n100 0 10\n0 5 10"
+            .parse()
+            .unwrap();
+        assert_eq!(part2(&almanac), Some(5));
+        assert_eq!(part2(&almanac), every_seed(&almanac));
+
+        // Rules anywhere in 0..64, overlapping however they like.
+        let mut rng = Lcg::new(5);
+        let mut random = |n: usize| rng.below(n);
+        for _ in 0..500 {
+            let layers: Vec<Layer> = (0..3)
+                .map(|i| Layer {
+                    source: ["seed", "a", "b"][i].to_owned(),
+                    destination: ["a", "b", "location"][i].to_owned(),
+                    rules: (0..1 + random(5))
+                        .map(|_| Rule {
+                            dst: random(64),
+                            src: random(64),
+                            len: random(20),
+                        })
+                        .collect(),
+                })
+                .collect();
+            let mut seeds = vec![];
+            for _ in 0..3 {
+                seeds.push(random(80));
+                seeds.push(1 + random(10));
+            }
+            let almanac = Almanac { seeds, layers };
+            assert_eq!(part2(&almanac), every_seed(&almanac), "{almanac:?}");
+            let f = almanac.function();
+            for i in 0..100 {
+                assert_eq!(f.get(i), seed_to_location(i, &almanac));
+            }
+        }
     }
 
     #[test]
     fn seeds_and_locations() {
-        let (_, layers) = parse(SAMPLE);
+        let almanac: Almanac = SAMPLE.parse().unwrap();
+        let f = almanac.function();
+        let g = f.inverse().unwrap();
         for i in 1..100 {
-            assert_eq!(i, location_to_seed(seed_to_location(i, &layers), &layers))
+            assert_eq!(i, location_to_seed(seed_to_location(i, &almanac), &almanac));
+            assert_eq!(f.get(i), seed_to_location(i, &almanac));
+            assert_eq!(i, g.get(f.get(i)));
         }
     }
+
+    #[test]
+    fn inspection() {
+        let almanac: Almanac = SAMPLE.parse().unwrap();
+        let soil = almanac.layer("seed-to-soil").unwrap().function();
+        assert_eq!(soil.breakpoints().collect::<Vec<_>>(), [50, 98, 100]);
+        assert_eq!(
+            [79, 14, 55, 13].map(|seed| soil.get(seed)),
+            [81, 14, 57, 13]
+        );
+        // 95..98 goes to 97..100, which touches 100..105.
+        assert_eq!(soil.image(&[95..100, 100..105]), [50..52, 97..105]);
+        assert_eq!(soil.image(&[10..20, 45..55]), [10..20, 45..50, 52..57]);
+
+        let f = almanac.between("water", "temperature").unwrap();
+        assert_eq!(f.get(81), 78);
+        assert!(almanac.between("location", "seed").is_err());
+        let circular: Almanac = "seeds: 1\n\na-to-b map:\n0 0 1\n\nb-to-a map:\n0 0 1"
+            .parse()
+            .unwrap();
+        assert!(circular.between("a", "b").is_ok());
+        assert!(circular.between("a", "c").is_err());
+
+        // Two rules onto the same place isn't invertible.
+        let layer = Layer {
+            source: "x".to_owned(),
+            destination: "y".to_owned(),
+            rules: vec![Rule {
+                dst: 0,
+                src: 10,
+                len: 5,
+            }],
+        };
+        assert_eq!(layer.function().inverse(), None);
+    }
 }
@@ -1,4 +1,6 @@
-use std::error::Error;
+use std::{error::Error, ops::RangeInclusive, str::FromStr};
+
+use num::{Integer, integer::Roots};
 
 pub const PUZZLE: &str = include_str!("../../puzzles/day06.txt");
 
@@ -8,12 +10,12 @@ fn main() {
     println!(
         "Part 2: {} ({})",
         part2(PUZZLE),
-        parse2(PUZZLE).unwrap().quadratic()
+        parse2::<u128>(PUZZLE).unwrap().quadratic()
     );
 }
 
-pub fn quadratic(input: &str) -> usize {
-    parse1(input)
+pub fn quadratic(input: &str) -> u128 {
+    parse1::<u128>(input)
         .unwrap()
         .iter()
         .map(|record| record.quadratic())
@@ -21,7 +23,7 @@ pub fn quadratic(input: &str) -> usize {
 }
 
 fn part1(input: &str) -> usize {
-    parse1(input)
+    parse1::<usize>(input)
         .unwrap()
         .iter()
         .map(|record| {
@@ -34,7 +36,7 @@ fn part1(input: &str) -> usize {
 }
 
 fn part2(input: &str) -> usize {
-    let Record { time, distance } = parse2(input).unwrap();
+    let Record { time, distance } = parse2::<usize>(input).unwrap();
     (0..=time).fold(0, |count, hold| {
         if (time - hold) * hold > distance {
             count + 1
@@ -44,26 +46,68 @@ fn part2(input: &str) -> usize {
     })
 }
 
+/// `u128` is exact as long as the time is below 2⁶⁴. Past that, use a
+/// `BigUint`.
 #[derive(PartialEq, Eq, Debug)]
-pub struct Record {
-    time: usize,
-    distance: usize,
+pub struct Record<T> {
+    time: T,
+    distance: T,
 }
 
-impl Record {
-    pub fn quadratic(&self) -> usize {
-        // Quadratic formula: x = (-b ± √(b² - 4ac)) / 2a
-        // d = h(t-h) → d = -h² + ht → 0 = -h² + ht - d
-        // ± (0177), → (8730), ² (0178), √ (251)
-        let a = -1.0;
-        let b = self.time as f64;
-        let c = -(self.distance as f64);
-        let x1 = ((-b - (b.powi(2) - 4.0 * a * c).sqrt()) / (2.0 * a)).ceil();
-        let x2 = ((-b + (b.powi(2) - 4.0 * a * c).sqrt()) / (2.0 * a)).floor();
-        let winners = (x1 - x2 - 1.0) as usize;
-        winners
+impl<T: Integer + Roots + Clone> Record<T> {
+    /// The hold times that beat the record.
+    ///
+    /// Quadratic formula: x = (-b ± √(b² - 4ac)) / 2a
+    /// d = h(t-h) → d = -h² + ht → 0 = -h² + ht - d
+    ///
+    /// The winners are strictly between the roots (t ± √(t² - 4d)) / 2. We
+    /// take the integer square root, which gets us to within one of the lower
+    /// bound, and then step to the exact answer. The curve is symmetric, so
+    /// the upper bound is t minus the lower.
+    pub fn winning_range(&self) -> Option<RangeInclusive<T>> {
+        let one = T::one();
+        let two = one.clone() + one.clone();
+        let four = two.clone() + two.clone();
+        let t = self.time.clone();
+        let beats = |h: &T| h.clone() * (t.clone() - h.clone()) > self.distance;
+
+        let square = t.clone() * t.clone();
+        let discriminant = four * self.distance.clone();
+        if square <= discriminant {
+            // At best we tie the record at t/2.
+            return None;
+        }
+        let root = (square - discriminant).sqrt();
+        let half = t.clone() / two.clone();
+        let mut lo = (t.clone() - root) / two;
+        while lo <= half && !beats(&lo) {
+            lo = lo + one.clone();
+        }
+        if lo > half {
+            // The roots are less than one apart and there's no integer
+            // between them.
+            return None;
+        }
+        while lo > T::zero() && beats(&(lo.clone() - one.clone())) {
+            lo = lo - one.clone();
+        }
+        let hi = t - lo.clone();
+        Some(lo..=hi)
     }
 
+    /// How many ways there are to win.
+    pub fn quadratic(&self) -> T {
+        match self.winning_range() {
+            Some(range) => {
+                let (lo, hi) = range.into_inner();
+                hi - lo + T::one()
+            }
+            None => T::zero(),
+        }
+    }
+}
+
+impl Record<usize> {
     fn _vertex(&self) -> f64 {
         // distance(h) = h(t-h) = -h²+ht+0
         // distance'(h) = -2h+t
@@ -75,15 +119,18 @@ impl Record {
     }
 }
 
-fn parse1(input: &str) -> Result<Vec<Record>, Box<dyn Error>> {
+fn parse1<T: FromStr>(input: &str) -> Result<Vec<Record<T>>, Box<dyn Error>>
+where
+    T::Err: Error + 'static,
+{
     if let [time, distance] = input.lines().collect::<Vec<_>>()[0..2] {
         let time = time.split_ascii_whitespace().skip(1);
         let distance = distance.split_ascii_whitespace().skip(1);
         time.zip(distance)
             .map(|(time, distance)| {
                 Ok(Record {
-                    time: time.parse::<usize>()?,
-                    distance: distance.parse::<usize>()?,
+                    time: time.parse::<T>()?,
+                    distance: distance.parse::<T>()?,
                 })
             })
             .collect()
@@ -92,11 +139,14 @@ fn parse1(input: &str) -> Result<Vec<Record>, Box<dyn Error>> {
     }
 }
 
-pub fn parse2(input: &str) -> Result<Record, Box<dyn Error>> {
+pub fn parse2<T: FromStr>(input: &str) -> Result<Record<T>, Box<dyn Error>>
+where
+    T::Err: Error + 'static,
+{
     let input = input.replace(" ", "");
     let mut input = input.split([':', '\n']);
     input.next();
-    let time = input.next().ok_or("no time")?.parse::<usize>()?;
+    let time = input.next().ok_or("no time")?.parse::<T>()?;
     input.next();
     let distance = input.next().ok_or("no distance")?.parse()?;
     Ok(Record { time, distance })
@@ -105,6 +155,8 @@ pub fn parse2(input: &str) -> Result<Record, Box<dyn Error>> {
 #[cfg(test)]
 mod day06 {
     use super::*;
+    use advent_of_code_2023::rng::Lcg;
+    use num::BigUint;
 
     const SAMPLE: &str = "Time:      7  15   30
 Distance:  9  40  200";
@@ -121,22 +173,108 @@ Distance:  9  40  200";
 
     #[test]
     fn test3() {
-        assert_eq!(part1(SAMPLE), quadratic(SAMPLE));
+        assert_eq!(part1(SAMPLE) as u128, quadratic(SAMPLE));
     }
 
     #[test]
     fn test4() {
-        assert_eq!(part2(SAMPLE), parse2(SAMPLE).unwrap().quadratic());
+        assert_eq!(
+            part2(SAMPLE) as u128,
+            parse2::<u128>(SAMPLE).unwrap().quadratic()
+        );
     }
 
     #[test]
     fn no_kerning() {
         assert_eq!(
-            parse2(SAMPLE).unwrap(),
+            parse2::<usize>(SAMPLE).unwrap(),
             Record {
                 time: 71530,
                 distance: 940200
             }
         )
     }
+
+    #[test]
+    fn exact_roots() {
+        // 10 and 20 tie the record, so they don't count.
+        let record = Record::<u128> {
+            time: 30,
+            distance: 200,
+        };
+        assert_eq!(record.winning_range(), Some(11..=19));
+        // The best we can do is tie.
+        let record = Record::<u128> {
+            time: 10,
+            distance: 25,
+        };
+        assert_eq!(record.winning_range(), None);
+        assert_eq!(record.quadratic(), 0);
+    }
+
+    #[test]
+    fn against_brute_force() {
+        let mut rng = Lcg::new(6);
+        let mut random = |n: u64| u64::from(rng.next_u32()) % n;
+        for _ in 0..500 {
+            let records: Vec<(u64, u64)> = (0..3)
+                .map(|_| {
+                    let time = random(100);
+                    (time, random(time * time / 4 + 3))
+                })
+                .collect();
+            let input = format!(
+                "Time: {}\nDistance: {}",
+                records
+                    .iter()
+                    .map(|r| r.0.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                records
+                    .iter()
+                    .map(|r| r.1.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+            assert_eq!(part1(&input) as u128, quadratic(&input), "{input}");
+            for (time, distance) in records {
+                let winners: Vec<u128> = (0..=time as u128)
+                    .filter(|hold| (time as u128 - hold) * hold > distance as u128)
+                    .collect();
+                let record = Record::<u128> {
+                    time: time.into(),
+                    distance: distance.into(),
+                };
+                assert_eq!(
+                    record.winning_range(),
+                    winners.first().map(|&lo| lo..=*winners.last().unwrap()),
+                    "{record:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn huge() {
+        // Far past where f64 can tell neighbouring integers apart.
+        let record = Record::<u128> {
+            time: 1 << 60,
+            distance: (1 << 118) - 1,
+        };
+        assert_eq!(record.winning_range(), Some((1 << 59)..=(1 << 59)));
+
+        let time = BigUint::from(10u8).pow(40);
+        let record = Record {
+            time: time.clone(),
+            distance: BigUint::from(123456789u32) * BigUint::from(10u8).pow(70),
+        };
+        let (lo, hi) = record.winning_range().unwrap().into_inner();
+        let beats = |h: &BigUint| h * (&time - h) > record.distance;
+        assert!(beats(&lo) && beats(&hi));
+        assert!(!beats(&(&lo - 1u8)) && !beats(&(&hi + 1u8)));
+        assert_eq!(
+            parse2::<BigUint>(SAMPLE).unwrap().quadratic(),
+            BigUint::from(71503u32)
+        );
+    }
 }
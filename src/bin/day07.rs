@@ -1,123 +1,296 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::{collections::HashMap, error::Error, fmt};
 
 use advent_of_code_2023::Part;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 pub const PUZZLE: &str = include_str!("../../puzzles/day07.txt");
 
-fn main() {
-    println!("Part 1: {}", solve(PUZZLE, Part::One));
-    println!("Part 2: {}", solve(PUZZLE, Part::Two));
+/// `--verify` checks the classifier against brute force for every possible
+/// hand instead.
+fn main() -> Result<(), Box<dyn Error>> {
+    if std::env::args().any(|arg| arg == "--verify") {
+        for part in [Part::One, Part::Two] {
+            let rules = Rules::camel_cards(part);
+            let disagreements = rules.verify();
+            for d in &disagreements {
+                println!("{d}");
+            }
+            println!(
+                "{} hands, {} disagreements",
+                rules.cards.len().pow(rules.hand_size as u32),
+                disagreements.len()
+            );
+        }
+        return Ok(());
+    }
+    println!(
+        "Part 1: {}",
+        Rules::camel_cards(Part::One).winnings(PUZZLE)?
+    );
+    println!(
+        "Part 2: {}",
+        Rules::camel_cards(Part::Two).winnings(PUZZLE)?
+    );
+    Ok(())
 }
 
 pub fn solve(input: &str, part: Part) -> usize {
-    let mut hands = input.lines().map(Hand::new).collect::<Vec<_>>();
-    let cards = match part {
-        Part::One => "23456789TJQKA",
-        Part::Two => "J23456789TQKA",
-    };
-    hands.sort_by(|a, b| {
-        let hand_comparison = match part {
-            Part::One => a.hand_type.cmp(&b.hand_type),
-            Part::Two => a.joker_type.cmp(&b.joker_type),
-        };
-        match hand_comparison {
-            Ordering::Equal => {
-                for (c1, c2) in a.hand.chars().zip(b.hand.chars()) {
-                    let v1 = cards.find(c1).unwrap();
-                    let v2 = cards.find(c2).unwrap();
-                    if v1 != v2 {
-                        return v1.cmp(&v2);
-                    }
-                }
-                Ordering::Equal
-            }
-            other => other,
-        }
-    });
-    // So funny story: the *weakest* hand gets rank 1.
-    // I was thinking strongest would be 1. If you
-    // reverse this, then the total winnings will be
-    // a maddening 6640 instead of 6440 on the test input.
-    hands
-        .into_iter()
-        .enumerate()
-        .fold(0, |acc, (rank, hand)| acc + (rank + 1) * hand.bid)
+    Rules::camel_cards(part).winnings(input).unwrap()
 }
 
-#[derive(Debug)]
-struct Hand {
-    hand: String,
-    bid: usize,
-    hand_type: HandType,
-    joker_type: HandType,
+/// A kind of hand, like a full house. `shape` is the group sizes it needs,
+/// largest first: a full house is `[3, 2]`. A hand with more than that still
+/// counts, so we always take the strongest category that fits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Category {
+    pub name: String,
+    pub shape: Vec<usize>,
 }
 
-impl Hand {
-    fn new(line: &str) -> Self {
-        match line.split_ascii_whitespace().collect::<Vec<_>>()[..] {
-            [hand, bid] if hand.len() == 5 => Self {
-                hand: hand.to_owned(),
-                bid: bid.parse::<usize>().expect("numeric bid"),
-                hand_type: HandType::get(hand),
-                joker_type: HandType::get(hand.replace("J", "").as_str()),
-            },
-            _ => panic!(),
+impl Category {
+    pub fn new(name: &str, shape: &[usize]) -> Self {
+        Self {
+            name: name.to_owned(),
+            shape: shape.to_vec(),
         }
     }
+
+    /// How many wildcards it would take to turn these groups (largest first)
+    /// into this shape. Putting the biggest groups in the biggest slots is
+    /// always the cheapest way.
+    fn deficit(&self, groups: &[usize]) -> usize {
+        self.shape
+            .iter()
+            .enumerate()
+            .map(|(i, &need)| need.saturating_sub(groups.get(i).copied().unwrap_or(0)))
+            .sum()
+    }
+}
+
+/// Everything that makes Camel Cards Camel Cards.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    /// Weakest first. This breaks ties between hands of the same category,
+    /// and wildcards keep their place in it.
+    pub cards: Vec<char>,
+    pub wild: Vec<char>,
+    pub hand_size: usize,
+    /// Weakest first.
+    pub categories: Vec<Category>,
 }
 
-#[derive(PartialEq, PartialOrd, Ord, Eq, Debug)]
-enum HandType {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
+/// A hand after ranking. The weakest hand has rank 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ranked {
+    pub hand: String,
+    pub bid: usize,
+    pub category: String,
+    pub rank: usize,
 }
 
-impl HandType {
-    fn get(hand: &str) -> Self {
+impl Rules {
+    /// The rules from the puzzle. In part 2, J is a joker: wild, but the
+    /// weakest card in a tie.
+    pub fn camel_cards(part: Part) -> Self {
+        let (cards, wild) = match part {
+            Part::One => ("23456789TJQKA", vec![]),
+            Part::Two => ("J23456789TQKA", vec!['J']),
+        };
+        Self {
+            cards: cards.chars().collect(),
+            wild,
+            hand_size: 5,
+            categories: vec![
+                Category::new("high card", &[1]),
+                Category::new("one pair", &[2]),
+                Category::new("two pair", &[2, 2]),
+                Category::new("three of a kind", &[3]),
+                Category::new("full house", &[3, 2]),
+                Category::new("four of a kind", &[4]),
+                Category::new("five of a kind", &[5]),
+            ],
+        }
+    }
+
+    /// The strongest category this hand fits, as an index into
+    /// `categories`.
+    pub fn classify(&self, hand: &str) -> Result<usize, Box<dyn Error>> {
         let mut tally: HashMap<char, usize> = HashMap::new();
+        let mut wild = 0;
         for c in hand.chars() {
-            *tally.entry(c).or_default() += 1;
+            if !self.cards.contains(&c) {
+                Err(format!("{c} is not a card in {hand}"))?;
+            }
+            if self.wild.contains(&c) {
+                wild += 1;
+            } else {
+                *tally.entry(c).or_default() += 1;
+            }
         }
-        let mut result = HandType::HighCard;
-        for value in tally.values() {
-            result = match value {
-                1 => result,
-                2 if result == HandType::OnePair => HandType::TwoPair,
-                2 if result == HandType::ThreeOfAKind => HandType::FullHouse,
-                2 => HandType::OnePair,
-                3 if result == HandType::OnePair => HandType::FullHouse,
-                3 => HandType::ThreeOfAKind,
-                4 => HandType::FourOfAKind,
-                5 => HandType::FiveOfAKind,
-                _ => panic!("unexpected tally in hand"),
-            };
+        if wild + tally.values().sum::<usize>() != self.hand_size {
+            Err(format!("{hand} is not {} cards", self.hand_size))?;
         }
+        let mut groups: Vec<usize> = tally.into_values().collect();
+        groups.sort_by(|a, b| b.cmp(a));
+        // Each group needs a rank of its own, and wildcards can't stand for
+        // themselves.
+        let ranks = self.cards.len() - self.wild.len();
+        self.categories
+            .iter()
+            .rposition(|category| {
+                category.shape.len() <= ranks && category.deficit(&groups) <= wild
+            })
+            .ok_or(format!("{hand} isn't in any category").into())
+    }
+
+    fn card(&self, c: char) -> usize {
+        self.cards.iter().position(|&card| card == c).unwrap()
+    }
 
-        // Part 2
-        let mut i = hand.len();
-        if i == 0 {
-            i = 1; // ugly handling for special case of JJJJJ
+    /// Sort the hands, weakest first: by category, then card by card.
+    pub fn rank(&self, input: &str) -> Result<Vec<Ranked>, Box<dyn Error>> {
+        let mut hands = vec![];
+        for line in input.lines() {
+            let [hand, bid] = line.split_ascii_whitespace().collect::<Vec<_>>()[..] else {
+                Err(format!("expected a hand and a bid: {line}"))?
+            };
+            let category = self.classify(hand)?;
+            let cards: Vec<usize> = hand.chars().map(|c| self.card(c)).collect();
+            hands.push(((category, cards), hand, bid.parse::<usize>()?));
         }
-        while i < 5 {
-            result = match result {
-                HandType::HighCard => HandType::OnePair,
-                HandType::OnePair => HandType::ThreeOfAKind,
-                HandType::TwoPair => HandType::FullHouse,
-                HandType::ThreeOfAKind => HandType::FourOfAKind,
-                HandType::FourOfAKind => HandType::FiveOfAKind,
-                HandType::FullHouse | HandType::FiveOfAKind => {
-                    dbg!(hand);
-                    unreachable!()
+        hands.sort();
+        // So funny story: the *weakest* hand gets rank 1.
+        // I was thinking strongest would be 1. If you
+        // reverse this, then the total winnings will be
+        // a maddening 6640 instead of 6440 on the test input.
+        Ok(hands
+            .into_iter()
+            .enumerate()
+            .map(|(i, ((category, _), hand, bid))| Ranked {
+                hand: hand.to_owned(),
+                bid,
+                category: self.categories[category].name.clone(),
+                rank: i + 1,
+            })
+            .collect())
+    }
+
+    pub fn winnings(&self, input: &str) -> Result<usize, Box<dyn Error>> {
+        Ok(self
+            .rank(input)?
+            .into_iter()
+            .map(|hand| hand.rank * hand.bid)
+            .sum())
+    }
+}
+
+/// A hand that [`Rules::classify`] and the brute force put in different
+/// categories.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disagreement {
+    pub hand: String,
+    pub engine: String,
+    pub oracle: String,
+    /// The stronger of the two categories, which one side thinks the hand
+    /// fits and the other doesn't.
+    pub rule: String,
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: classified as {}, but brute force says {} (diverged on {})",
+            self.hand, self.engine, self.oracle, self.rule
+        )
+    }
+}
+
+// Only used by `--verify` and the tests.
+#[allow(dead_code)]
+impl Rules {
+    /// Try every possible hand. The classifier works out how many wildcards
+    /// each category would take; the brute force just tries every card in
+    /// place of every wildcard and keeps the best.
+    pub fn verify(&self) -> Vec<Disagreement> {
+        let n = self.cards.len();
+        let mut disagreements: Vec<_> = (0..n.pow(self.hand_size as u32))
+            .into_par_iter()
+            .filter_map(|mut i| {
+                let mut hand = String::new();
+                for _ in 0..self.hand_size {
+                    hand.push(self.cards[i % n]);
+                    i /= n;
                 }
-            };
-            i += 1;
+                let engine = self.classify(&hand).ok();
+                let oracle = self.brute_force(&hand);
+                (engine != oracle).then(|| {
+                    let rule = &self.categories[engine.max(oracle).unwrap()];
+                    Disagreement {
+                        engine: self.name(engine),
+                        oracle: self.name(oracle),
+                        rule: format!("{} {:?}", rule.name, rule.shape),
+                        hand,
+                    }
+                })
+            })
+            .collect();
+        disagreements.sort_by(|a, b| a.hand.cmp(&b.hand));
+        disagreements
+    }
+
+    fn name(&self, category: Option<usize>) -> String {
+        category.map_or("nothing".to_owned(), |c| self.categories[c].name.clone())
+    }
+
+    fn brute_force(&self, hand: &str) -> Option<usize> {
+        let natural: Vec<usize> = (0..self.cards.len())
+            .filter(|&c| !self.wild.contains(&self.cards[c]))
+            .collect();
+        let mut hand: Vec<usize> = hand.chars().map(|c| self.card(c)).collect();
+        let wild: Vec<usize> = (0..hand.len())
+            .filter(|&i| !natural.contains(&hand[i]))
+            .collect();
+        if !wild.is_empty() && natural.is_empty() {
+            return None;
+        }
+        let mut counts = vec![0; self.cards.len()];
+        self.substitute(&mut hand, &wild, &natural, &mut counts)
+    }
+
+    /// Which wildcard becomes which card doesn't matter, only how many of
+    /// each, so we only try substitutions in nondecreasing order.
+    fn substitute(
+        &self,
+        hand: &mut [usize],
+        wild: &[usize],
+        natural: &[usize],
+        counts: &mut [usize],
+    ) -> Option<usize> {
+        let Some((&w, wild)) = wild.split_first() else {
+            return self.exact(hand, counts);
+        };
+        let mut best = None;
+        for i in 0..natural.len() {
+            hand[w] = natural[i];
+            best = best.max(self.substitute(hand, wild, &natural[i..], counts));
         }
-        result
+        best
+    }
+
+    /// The strongest category this hand has without any wildcards.
+    fn exact(&self, hand: &[usize], counts: &mut [usize]) -> Option<usize> {
+        counts.fill(0);
+        for &c in hand {
+            counts[c] += 1;
+        }
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        let groups = &counts[..counts.iter().take_while(|&&n| n > 0).count()];
+        self.categories.iter().rposition(|category| {
+            category.shape.len() <= groups.len()
+                && category.shape.iter().zip(groups).all(|(s, g)| g >= s)
+        })
     }
 }
 
@@ -143,14 +316,121 @@ mod day07 {
 
     #[test]
     fn test_types() {
-        for (hand, hand_type) in [
-            ("32T3K", HandType::OnePair),
-            ("KK677", HandType::TwoPair),
-            ("KTJJT", HandType::TwoPair),
-            ("T55J5", HandType::ThreeOfAKind),
-            ("QQQJA", HandType::ThreeOfAKind),
+        let rules = Rules::camel_cards(Part::One);
+        let jokers = Rules::camel_cards(Part::Two);
+        for (hand, category, joker_category) in [
+            ("32T3K", "one pair", "one pair"),
+            ("KK677", "two pair", "two pair"),
+            ("KTJJT", "two pair", "four of a kind"),
+            ("T55J5", "three of a kind", "four of a kind"),
+            ("QQQJA", "three of a kind", "four of a kind"),
+            ("JJJJJ", "five of a kind", "five of a kind"),
+            ("2345J", "high card", "one pair"),
+            ("22JJ3", "two pair", "four of a kind"),
         ] {
-            assert_eq!(HandType::get(hand), hand_type)
+            assert_eq!(
+                rules.categories[rules.classify(hand).unwrap()].name,
+                category
+            );
+            assert_eq!(
+                jokers.categories[jokers.classify(hand).unwrap()].name,
+                joker_category
+            );
         }
+        assert!(rules.classify("2345").is_err());
+        assert!(rules.classify("2345X").is_err());
+    }
+
+    #[test]
+    fn ranks() {
+        let ranked = Rules::camel_cards(Part::Two).rank(SAMPLE).unwrap();
+        let summary: Vec<_> = ranked
+            .iter()
+            .map(|r| (r.hand.as_str(), r.category.as_str(), r.rank))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("32T3K", "one pair", 1),
+                ("KK677", "two pair", 2),
+                ("T55J5", "four of a kind", 3),
+                ("QQQJA", "four of a kind", 4),
+                ("KTJJT", "four of a kind", 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn variants() {
+        // Seven cards, with deuces and jokers both wild.
+        let mut rules = Rules::camel_cards(Part::Two);
+        rules.cards.insert(0, '*');
+        rules.wild = vec!['*', '2'];
+        rules.hand_size = 7;
+        rules.categories = vec![
+            Category::new("high card", &[1]),
+            Category::new("pair", &[2]),
+            Category::new("three pair", &[2, 2, 2]),
+            Category::new("full house", &[3, 2]),
+            Category::new("four and three", &[4, 3]),
+            Category::new("seven of a kind", &[7]),
+        ];
+        let name = |hand| &rules.categories[rules.classify(hand).unwrap()].name;
+        assert_eq!(name("3456789"), "high card");
+        assert_eq!(name("3456782"), "pair");
+        assert_eq!(name("3344556"), "three pair");
+        assert_eq!(name("3344562"), "full house");
+        assert_eq!(name("33344*2"), "four and three");
+        assert_eq!(name("*222222"), "seven of a kind");
+        let ranked = rules.rank("3456789 1\n*345678 10\n3456782 100").unwrap();
+        // The joker is the weakest card, so it loses the tie with the deuce.
+        assert_eq!(ranked[1].hand, "*345678");
+        assert_eq!(
+            rules
+                .winnings("3456789 1\n*345678 10\n3456782 100")
+                .unwrap(),
+            321
+        );
+    }
+
+    #[test]
+    fn oracle() {
+        assert_eq!(Rules::camel_cards(Part::One).verify(), []);
+        assert_eq!(Rules::camel_cards(Part::Two).verify(), []);
+        // Four card hands with two wild ranks.
+        let mut rules = Rules::camel_cards(Part::Two);
+        rules.wild.push('2');
+        rules.hand_size = 4;
+        assert_eq!(rules.verify(), []);
+    }
+
+    #[test]
+    fn too_few_ranks() {
+        // Three different cards would need three ranks that aren't wild.
+        let rules = Rules {
+            cards: "J23".chars().collect(),
+            wild: vec!['J'],
+            hand_size: 3,
+            categories: vec![
+                Category::new("high card", &[1]),
+                Category::new("pair", &[2]),
+                Category::new("rainbow", &[1, 1, 1]),
+            ],
+        };
+        assert_eq!(
+            rules.categories[rules.classify("2JJ").unwrap()].name,
+            "pair"
+        );
+        assert_eq!(rules.verify(), []);
+        let disagreement = Disagreement {
+            hand: "2JJ".to_owned(),
+            engine: "rainbow".to_owned(),
+            oracle: "pair".to_owned(),
+            rule: "rainbow [1, 1, 1]".to_owned(),
+        };
+        assert_eq!(
+            disagreement.to_string(),
+            "2JJ: classified as rainbow, but brute force says pair (diverged on rainbow [1, 1, 1])"
+        );
     }
 }
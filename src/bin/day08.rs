@@ -1,16 +1,74 @@
-use core::panic;
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    error::Error,
+    fmt,
+    fmt::Write,
+};
 
-use num::Integer;
+use itertools::Itertools;
+use num::{Integer, integer::ExtendedGcd};
+use petgraph::{algo::tarjan_scc, prelude::DiGraphMap};
 
 pub const PUZZLE: &str = include_str!("../../puzzles/day08.txt");
 
-fn main() {
-    let n = Network::new(PUZZLE);
-    println!("Part 1: {}", n.zzz("AAA").unwrap());
-    println!("Part 2: {}", n.part2_lcm());
+/// `--graphviz` prints the network for `dot` instead.
+fn main() -> Result<(), Box<dyn Error>> {
+    let n = Network::new(PUZZLE)?;
+    if std::env::args().any(|arg| arg == "--graphviz") {
+        print!("{}", n.graphviz());
+        return Ok(());
+    }
+    println!("Part 1: {}", n.zzz("AAA")?);
+    println!("Part 2: {}", n.part2().ok_or("the ghosts never line up")?);
+    print!("{}", Assumptions::new(&n.cycles()));
+    print!("{}", n.diagnostics());
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkError {
+    NoTurns,
+    /// Position counts from 0.
+    BadTurn {
+        turn: char,
+        position: usize,
+    },
+    /// Line numbers count from 1.
+    BadLine {
+        line: usize,
+        text: String,
+    },
+    Duplicate {
+        line: usize,
+        node: String,
+    },
+    /// A node that is used but never defined.
+    UnknownNode(String),
+    /// There's no Z node on the way from this start.
+    NeverArrives(String),
 }
 
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::NoTurns => write!(f, "the first line should be the turns"),
+            NetworkError::BadTurn { turn, position } => {
+                write!(f, "turn {position} is {turn:?}, not L or R")
+            }
+            NetworkError::BadLine { line, text } => {
+                write!(f, "line {line} is not `NODE = (LEFT, RIGHT)`: {text}")
+            }
+            NetworkError::Duplicate { line, node } => {
+                write!(f, "line {line} defines {node} again")
+            }
+            NetworkError::UnknownNode(node) => write!(f, "node {node} is never defined"),
+            NetworkError::NeverArrives(node) => write!(f, "there is no Z node after {node}"),
+        }
+    }
+}
+
+impl Error for NetworkError {}
+
 #[derive(Debug)]
 pub struct Network<'a> {
     turns: String,
@@ -18,47 +76,85 @@ pub struct Network<'a> {
 }
 
 impl<'a> Network<'a> {
-    pub fn new(input: &'a str) -> Self {
-        let mut lines = input.lines();
-        let turns = lines.next().unwrap().to_owned();
+    /// Node names can be any length, and can be any characters except
+    /// whitespace and `=(),`.
+    pub fn new(input: &'a str) -> Result<Self, NetworkError> {
+        let mut lines = input.lines().enumerate();
+        let turns = lines.next().map(|(_, line)| line.trim()).unwrap_or("");
+        if turns.is_empty() {
+            return Err(NetworkError::NoTurns);
+        }
+        if let Some((position, turn)) = turns
+            .chars()
+            .enumerate()
+            .find(|&(_, c)| c != 'L' && c != 'R')
+        {
+            return Err(NetworkError::BadTurn { turn, position });
+        }
         let mut directions = HashMap::default();
-        lines.next(); // discard blank line
-        for line in lines {
-            let a = &line[0..3];
-            let left = &line[7..10];
-            let right = &line[12..15];
-            directions.insert(a, Position { left, right });
+        for (i, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let bad = || NetworkError::BadLine {
+                line: i + 1,
+                text: line.to_owned(),
+            };
+            let (node, exits) = line.split_once('=').ok_or_else(bad)?;
+            let (left, right) = exits
+                .trim()
+                .strip_prefix('(')
+                .and_then(|exits| exits.strip_suffix(')'))
+                .and_then(|exits| exits.split_once(','))
+                .ok_or_else(bad)?;
+            let [node, left, right] = [node, left, right].map(str::trim);
+            if [node, left, right].iter().any(|name| {
+                name.is_empty() || name.contains(|c: char| c.is_whitespace() || "=(),".contains(c))
+            }) {
+                return Err(bad());
+            }
+            if directions.insert(node, Position { left, right }).is_some() {
+                return Err(NetworkError::Duplicate {
+                    line: i + 1,
+                    node: node.to_owned(),
+                });
+            }
         }
-        Self { turns, directions }
+        for d in directions.values() {
+            for exit in [d.left, d.right] {
+                if !directions.contains_key(exit) {
+                    return Err(NetworkError::UnknownNode(exit.to_owned()));
+                }
+            }
+        }
+        Ok(Self {
+            turns: turns.to_owned(),
+            directions,
+        })
     }
 
-    pub fn zzz(&self, start: &str) -> Result<usize, ()> {
-        let mut p = start;
-        let mut i = 0;
-        for c in self.turns.chars().cycle() {
-            if p.ends_with("Z") {
-                return Ok(i);
-            }
-            let d = self.directions.get(p).unwrap();
-            p = match c {
-                'L' => d.left,
-                'R' => d.right,
-                _ => panic!(),
-            };
-            i += 1;
+    /// Steps from `start` to the first node ending in Z.
+    pub fn zzz(&self, start: &str) -> Result<usize, NetworkError> {
+        if !self.directions.contains_key(start) {
+            return Err(NetworkError::UnknownNode(start.to_owned()));
         }
-        Err(())
+        let cycle = self.cycle(start);
+        cycle
+            .prefix_hits
+            .first()
+            .or(cycle.cycle_hits.first())
+            .copied()
+            .ok_or(NetworkError::NeverArrives(start.to_owned()))
     }
 
-    #[allow(dead_code)]
-    fn part2_naive(&self) -> Result<usize, ()> {
+    /// Walk every ghost at once, for at most `limit` steps.
+    fn part2_naive(&self, limit: usize) -> Result<usize, ()> {
         let mut p: Vec<_> = self
             .directions
             .keys()
             .filter(|s| s.ends_with("A"))
             .collect();
-        let mut i = 0;
-        for c in self.turns.chars().cycle() {
+        for (i, c) in self.turns.chars().cycle().take(limit).enumerate() {
             if p.iter().all(|s| s.ends_with("Z")) {
                 return Ok(i);
             }
@@ -67,15 +163,17 @@ impl<'a> Network<'a> {
                 *ghostp = match c {
                     'L' => &d.left,
                     'R' => &d.right,
-                    _ => panic!(),
+                    _ => unreachable!("turns are checked when parsing"),
                 };
             }
-            i += 1;
         }
 
         Err(())
     }
 
+    /// This is only right because of how the puzzle inputs are made: see
+    /// [`Assumptions`].
+    #[allow(dead_code)]
     pub fn part2_lcm(&self) -> usize {
         self.directions
             .keys()
@@ -83,6 +181,338 @@ impl<'a> Network<'a> {
             .map(|k| self.zzz(k).unwrap())
             .fold(1, |acc, d| acc.lcm(&d))
     }
+
+    /// Part 2 for any network. If everything repeats within a few thousand
+    /// steps we just walk it; otherwise we work it out from the cycles.
+    pub fn part2(&self) -> Option<usize> {
+        let cycles = self.cycles();
+        let prefix = cycles.iter().map(|c| c.prefix).max()?;
+        let period = cycles.iter().fold(1, |acc: usize, c| acc.lcm(&c.length));
+        match prefix.checked_add(period) {
+            // Any answer repeats every period once all the ghosts are in
+            // their cycles, so the first one comes before prefix + period.
+            Some(limit) if limit <= TINY => self.part2_naive(limit).ok(),
+            _ => simultaneous(&cycles),
+        }
+    }
+
+    /// Follow one ghost until it's back in a (node, turn) state it has
+    /// already been in.
+    pub fn cycle(&self, start: &str) -> Cycle {
+        let turns = self.turns.as_bytes();
+        let mut seen = HashMap::new();
+        let mut hits = vec![];
+        let mut node = start;
+        let mut step = 0;
+        let (prefix, length) = loop {
+            let turn = step % turns.len();
+            if let Some(&first) = seen.get(&(node, turn)) {
+                break (first, step - first);
+            }
+            seen.insert((node, turn), step);
+            if node.ends_with("Z") {
+                hits.push(step);
+            }
+            let d = &self.directions[node];
+            node = match turns[turn] {
+                b'L' => d.left,
+                b'R' => d.right,
+                _ => unreachable!("turns are checked when parsing"),
+            };
+            step += 1;
+        };
+        let (prefix_hits, cycle_hits) = hits.into_iter().partition(|&hit| hit < prefix);
+        Cycle {
+            start: start.to_owned(),
+            prefix,
+            length,
+            prefix_hits,
+            cycle_hits,
+        }
+    }
+
+    /// The cycle of every ghost, in order of their starting nodes.
+    pub fn cycles(&self) -> Vec<Cycle> {
+        self.directions
+            .keys()
+            .filter(|k| k.ends_with("A"))
+            .sorted()
+            .map(|k| self.cycle(k))
+            .collect()
+    }
+}
+
+// The TUI doesn't look at these.
+#[allow(dead_code)]
+impl Network<'_> {
+    /// Things that might be wrong with a map, ignoring the order of the
+    /// turns.
+    pub fn diagnostics(&self) -> Diagnostics {
+        let mut graph: DiGraphMap<&str, ()> = DiGraphMap::new();
+        let mut backward: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (&node, d) in &self.directions {
+            graph.add_node(node);
+            for exit in [d.left, d.right] {
+                graph.add_edge(node, exit, ());
+                backward.entry(exit).or_default().push(node);
+            }
+        }
+        let starts = self.directions.keys().filter(|k| k.ends_with("A"));
+        let forward = reachable(starts.copied(), |node| {
+            let d = &self.directions[node];
+            vec![d.left, d.right]
+        });
+        let ends = self.directions.keys().filter(|k| k.ends_with("Z"));
+        let finishing = reachable(ends.copied(), |node| {
+            backward.get(node).cloned().unwrap_or_default()
+        });
+
+        let names = |nodes: &mut dyn Iterator<Item = &&str>| -> Vec<String> {
+            nodes.map(|node| node.to_string()).sorted().collect()
+        };
+        let self_loops = |node: &&&str| {
+            let d = &self.directions[**node];
+            d.left == **node || d.right == **node
+        };
+        let mut components: Vec<Vec<String>> = tarjan_scc(&graph)
+            .into_iter()
+            .filter(|scc| scc.len() > 1 || self_loops(&&scc[0]))
+            .map(|scc| names(&mut scc.iter()))
+            .collect();
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        Diagnostics {
+            unreachable: names(&mut self.directions.keys().filter(|k| !forward.contains(*k))),
+            dead_ends: names(&mut self.directions.keys().filter(|k| !finishing.contains(*k))),
+            self_loops: names(&mut self.directions.keys().filter(self_loops)),
+            components,
+        }
+    }
+
+    /// The map for Graphviz, with the start nodes in green and the end nodes
+    /// in red.
+    pub fn graphviz(&self) -> String {
+        let quote = |name: &str| format!("\"{}\"", name.replace('"', "\\\""));
+        let mut s = String::from("digraph network {\n");
+        for node in self.directions.keys().sorted() {
+            let color = if node.ends_with("A") {
+                "palegreen"
+            } else if node.ends_with("Z") {
+                "lightcoral"
+            } else {
+                continue;
+            };
+            writeln!(s, "\t{} [style=filled, fillcolor={color}];", quote(node)).unwrap();
+        }
+        for (node, d) in self.directions.iter().sorted_by_key(|(node, _)| **node) {
+            if d.left == d.right {
+                writeln!(s, "\t{} -> {} [label=\"LR\"];", quote(node), quote(d.left)).unwrap();
+            } else {
+                writeln!(s, "\t{} -> {} [label=\"L\"];", quote(node), quote(d.left)).unwrap();
+                writeln!(s, "\t{} -> {} [label=\"R\"];", quote(node), quote(d.right)).unwrap();
+            }
+        }
+        s.push_str("}\n");
+        s
+    }
+}
+
+/// Everything we can get to from `starts`.
+#[allow(dead_code)]
+fn reachable<'a>(
+    starts: impl Iterator<Item = &'a str>,
+    next: impl Fn(&'a str) -> Vec<&'a str>,
+) -> BTreeSet<&'a str> {
+    let mut seen: BTreeSet<&str> = starts.collect();
+    let mut queue: VecDeque<&str> = seen.iter().copied().collect();
+    while let Some(node) = queue.pop_front() {
+        for next in next(node) {
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// See [`Network::diagnostics`]. Node names are sorted.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Nodes that no ghost can get to from any start.
+    pub unreachable: Vec<String>,
+    /// Nodes that can't get to any Z node.
+    pub dead_ends: Vec<String>,
+    /// Nodes where at least one turn leads back to the same node.
+    pub self_loops: Vec<String>,
+    /// Strongly connected components that can trap a ghost: more than one
+    /// node, or one with a self-loop. Largest first.
+    pub components: Vec<Vec<String>>,
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Unreachable: {}", self.unreachable.join(" "))?;
+        writeln!(f, "Dead ends: {}", self.dead_ends.join(" "))?;
+        writeln!(f, "Self-loops: {}", self.self_loops.join(" "))?;
+        for component in &self.components {
+            writeln!(
+                f,
+                "Component of {}: {}",
+                component.len(),
+                component.join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The most steps [`Network::part2`] will walk before doing the math instead.
+const TINY: usize = 100_000;
+
+/// A ghost's walk: some steps to get into a cycle, and then around it forever.
+/// Steps are counted from the start, so `cycle_hits` are all in
+/// `prefix..prefix + length`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: String,
+    pub prefix: usize,
+    pub length: usize,
+    /// When we're on a Z node before the cycle. These only happen once.
+    pub prefix_hits: Vec<usize>,
+    /// When we're on a Z node the first time around the cycle. These happen
+    /// again every `length` steps.
+    pub cycle_hits: Vec<usize>,
+}
+
+impl Cycle {
+    pub fn is_hit(&self, step: usize) -> bool {
+        if step < self.prefix {
+            self.prefix_hits.contains(&step)
+        } else {
+            let offset = (step - self.prefix) % self.length;
+            self.cycle_hits.contains(&(self.prefix + offset))
+        }
+    }
+
+    fn hits_before(&self, end: usize) -> impl Iterator<Item = usize> + '_ {
+        let repeats = self
+            .cycle_hits
+            .iter()
+            .flat_map(move |&hit| (hit..end).step_by(self.length));
+        self.prefix_hits
+            .iter()
+            .copied()
+            .chain(repeats)
+            .filter(move |&hit| hit < end)
+            .sorted()
+    }
+}
+
+/// The first step where every ghost is on a Z node at once.
+pub fn simultaneous(cycles: &[Cycle]) -> Option<usize> {
+    let start = cycles.iter().map(|c| c.prefix).max()?;
+    // Until every ghost is in its cycle, go through the first ghost's hits.
+    for step in cycles[0].hits_before(start) {
+        if cycles.iter().all(|c| c.is_hit(step)) {
+            return Some(step);
+        }
+    }
+    // After that, each ghost needs the step to line up with one of its
+    // cycle hits. Try every combination.
+    cycles
+        .iter()
+        .map(|c| c.cycle_hits.iter())
+        .multi_cartesian_product()
+        .filter_map(|hits| {
+            let (r, m) = crt(hits
+                .into_iter()
+                .zip(cycles)
+                .map(|(&hit, c)| (hit, c.length)))?;
+            if r >= start {
+                Some(r)
+            } else {
+                r.checked_add((start - r).div_ceil(m).checked_mul(m)?)
+            }
+        })
+        .min()
+}
+
+/// The generalised Chinese remainder theorem: the x in 0..m, where m is the
+/// LCM of the moduli, such that x ≡ a (mod n) for every (a, n). The moduli
+/// don't have to be coprime, but then there might not be a solution.
+pub fn crt(system: impl IntoIterator<Item = (usize, usize)>) -> Option<(usize, usize)> {
+    let (mut r, mut m) = (0i128, 1i128);
+    for (a, n) in system {
+        let n = n as i128;
+        let a = a as i128 % n;
+        let ExtendedGcd { gcd: g, x, .. } = m.extended_gcd(&n);
+        if (a - r) % g != 0 {
+            return None;
+        }
+        // r + m·k ≡ a (mod n), so k ≡ (a - r)/g · x (mod n/g).
+        let step = n / g;
+        let k = mul_mod((a - r) / g, x, step);
+        let lcm = m.checked_mul(step)?;
+        r = (r + m * k).rem_euclid(lcm);
+        m = lcm;
+    }
+    Some((r.try_into().ok()?, m.try_into().ok()?))
+}
+
+/// a·b mod m, without overflowing. Our moduli come from `usize`, so both
+/// factors fit in 64 bits once they're reduced and the product fits in a
+/// `u128`.
+fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    let m = u64::try_from(m).expect("a modulus from a usize");
+    let (a, b) = (
+        a.rem_euclid(m.into()) as u128,
+        b.rem_euclid(m.into()) as u128,
+    );
+    (a * b % u128::from(m)) as i128
+}
+
+/// What [`Network::part2_lcm`] takes for granted. The official inputs have
+/// all three.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assumptions {
+    /// No ghost passes a Z node before it gets into its cycle.
+    pub no_prefix_hits: bool,
+    /// Each ghost passes exactly one Z node per cycle.
+    pub one_hit_per_cycle: bool,
+    /// That Z node is a whole number of cycles from the start, so the answer
+    /// is a multiple of every cycle length.
+    pub zero_offset: bool,
+}
+
+#[allow(dead_code)]
+impl Assumptions {
+    pub fn new(cycles: &[Cycle]) -> Self {
+        Self {
+            no_prefix_hits: cycles.iter().all(|c| c.prefix_hits.is_empty()),
+            one_hit_per_cycle: cycles.iter().all(|c| c.cycle_hits.len() == 1),
+            zero_offset: cycles
+                .iter()
+                .all(|c| c.cycle_hits.iter().all(|hit| hit % c.length == 0)),
+        }
+    }
+
+    pub fn lcm_works(&self) -> bool {
+        self.no_prefix_hits && self.one_hit_per_cycle && self.zero_offset
+    }
+}
+
+impl fmt::Display for Assumptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let check = |held| if held { "✓" } else { "✗" };
+        writeln!(f, "{} no Z before the cycle", check(self.no_prefix_hits))?;
+        writeln!(f, "{} one Z per cycle", check(self.one_hit_per_cycle))?;
+        writeln!(
+            f,
+            "{} Z at a multiple of the cycle length",
+            check(self.zero_offset)
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -94,6 +524,7 @@ struct Position<'a> {
 #[cfg(test)]
 mod day08 {
     use super::*;
+    use advent_of_code_2023::rng::Lcg;
 
     const SAMPLE1: &str = "RL
 
@@ -124,25 +555,205 @@ XXX = (XXX, XXX)";
 
     #[test]
     fn test1() {
-        let n = Network::new(SAMPLE1);
+        let n = Network::new(SAMPLE1).unwrap();
         assert_eq!(n.zzz("AAA"), Ok(2));
     }
 
     #[test]
     fn test2() {
-        let n = Network::new(SAMPLE2);
+        let n = Network::new(SAMPLE2).unwrap();
         assert_eq!(n.zzz("AAA"), Ok(6));
     }
 
     #[test]
     fn test3() {
-        let n = Network::new(SAMPLE3);
-        assert_eq!(n.part2_naive(), Ok(6));
+        let n = Network::new(SAMPLE3).unwrap();
+        assert_eq!(n.part2_naive(100), Ok(6));
+        assert_eq!(n.part2(), Some(6));
     }
 
     #[test]
     fn test4() {
-        let n = Network::new(SAMPLE3);
-        assert_eq!(n.part2_naive().unwrap(), n.part2_lcm());
+        let n = Network::new(SAMPLE3).unwrap();
+        assert_eq!(n.part2_naive(100).unwrap(), n.part2_lcm());
+        assert_eq!(simultaneous(&n.cycles()), Some(6));
+    }
+
+    #[test]
+    fn cycles() {
+        let n = Network::new(SAMPLE3).unwrap();
+        let cycles = n.cycles();
+        assert_eq!(
+            cycles[0],
+            Cycle {
+                start: "11A".to_owned(),
+                prefix: 1,
+                length: 2,
+                prefix_hits: vec![],
+                cycle_hits: vec![2],
+            }
+        );
+        assert_eq!((cycles[1].prefix, cycles[1].length), (1, 6));
+        assert_eq!(cycles[1].cycle_hits, [3, 6]);
+        let assumptions = Assumptions::new(&cycles);
+        assert!(assumptions.no_prefix_hits);
+        assert!(!assumptions.one_hit_per_cycle);
+        assert!(!assumptions.lcm_works());
+    }
+
+    #[test]
+    fn chinese_remainders() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt([(1, 4), (3, 6)]), Some((9, 12)));
+        assert_eq!(crt([(0, 4), (1, 6)]), None);
+        assert_eq!(crt([]), Some((0, 1)));
+
+        // Primes just under 2^32, so the LCM only just fits.
+        let (p, q) = (4294967291, 4294967279);
+        let (x, m) = crt([(p - 1, p), (q - 2, q)]).unwrap();
+        assert_eq!((x % p, x % q, m), (p - 1, q - 2, p * q));
+        // Intermediate products near 2^127, and an answer that doesn't fit.
+        let n = 18446744073709551557;
+        for a in 2..6 {
+            assert_eq!(crt([(1, a), (n - 1, n)]), None);
+        }
+    }
+
+    #[test]
+    fn offsets() {
+        // 11A hits a Z on the way in and then every 3 steps after 4;
+        // 22A every 2 steps. The LCM of the first hits would say 2.
+        let n = Network::new(
+            "L
+
+11A = (11Z, XXX)
+11Z = (11B, XXX)
+11B = (11C, XXX)
+11C = (11D, XXX)
+11D = (11E, XXX)
+11E = (11B, XXX)
+22A = (22Z, XXX)
+22Z = (22A, XXX)
+XXX = (XXX, XXX)",
+        )
+        .unwrap();
+        let cycles = n.cycles();
+        assert_eq!(cycles[0].prefix_hits, [1]);
+        assert!(!Assumptions::new(&cycles).lcm_works());
+        assert_eq!(simultaneous(&cycles), Some(1));
+        assert_eq!(n.part2(), Some(1));
+    }
+
+    #[test]
+    fn against_naive() {
+        let mut rng = Lcg::new(8);
+        let mut random = |n: usize| rng.below(n);
+        for _ in 0..300 {
+            let names: Vec<String> = (0..10)
+                .map(|i| format!("N{i}{}", ['A', 'Z', 'B', 'Z', 'C'][random(5)]))
+                .collect();
+            let turns: String = (0..1 + random(4))
+                .map(|_| if random(2) == 0 { 'L' } else { 'R' })
+                .collect();
+            let mut input = format!("{turns}\n");
+            for name in &names {
+                input += &format!("\n{name} = ({}, {})", names[random(10)], names[random(10)]);
+            }
+            let n = Network::new(&input).unwrap();
+            let cycles = n.cycles();
+            if cycles.is_empty() {
+                continue;
+            }
+            let expected = n.part2_naive(10_000).ok();
+            assert_eq!(simultaneous(&cycles), expected, "{input}");
+            if Assumptions::new(&cycles).lcm_works() {
+                assert_eq!(Some(n.part2_lcm()), expected, "{input}");
+            }
+        }
+    }
+
+    #[test]
+    fn parsing() {
+        let n = Network::new("LL\n\nstart-A = ( here ,Z)\nhere = (Z, here)\nZ = (Z,Z)\n").unwrap();
+        assert_eq!(n.zzz("start-A"), Ok(2));
+        assert_eq!(
+            n.zzz("nowhere"),
+            Err(NetworkError::UnknownNode("nowhere".to_owned()))
+        );
+        let n = Network::new(SAMPLE1).unwrap();
+        assert_eq!(
+            n.zzz("DDD"),
+            Err(NetworkError::NeverArrives("DDD".to_owned()))
+        );
+
+        assert_eq!(Network::new("").unwrap_err(), NetworkError::NoTurns);
+        assert_eq!(
+            Network::new("LRX\n\nAAA = (AAA, AAA)").unwrap_err(),
+            NetworkError::BadTurn {
+                turn: 'X',
+                position: 2
+            }
+        );
+        assert_eq!(
+            Network::new("L\n\nAAA = AAA, AAA").unwrap_err(),
+            NetworkError::BadLine {
+                line: 3,
+                text: "AAA = AAA, AAA".to_owned()
+            }
+        );
+        assert_eq!(
+            Network::new("L\n\nAAA = (AAA, AAA)\nAAA = (AAA, AAA)").unwrap_err(),
+            NetworkError::Duplicate {
+                line: 4,
+                node: "AAA".to_owned()
+            }
+        );
+        assert_eq!(
+            Network::new("L\n\nAAA = (BBB, AAA)")
+                .unwrap_err()
+                .to_string(),
+            "node BBB is never defined"
+        );
+    }
+
+    #[test]
+    fn diagnostics() {
+        let d = Network::new(SAMPLE1).unwrap().diagnostics();
+        assert_eq!(d.unreachable, Vec::<String>::new());
+        assert_eq!(d.dead_ends, ["BBB", "DDD", "EEE", "GGG"]);
+        assert_eq!(d.self_loops, ["DDD", "EEE", "GGG", "ZZZ"]);
+        assert_eq!(d.components, [["DDD"], ["EEE"], ["GGG"], ["ZZZ"]]);
+
+        let d = Network::new(SAMPLE3).unwrap().diagnostics();
+        assert_eq!(d.dead_ends, ["XXX"]);
+        assert_eq!(d.self_loops, ["XXX"]);
+        assert_eq!(
+            d.components,
+            [vec!["22B", "22C", "22Z"], vec!["11B", "11Z"], vec!["XXX"]]
+        );
+
+        let d = Network::new(SAMPLE2).unwrap().diagnostics();
+        assert_eq!(d.components, [vec!["AAA", "BBB"], vec!["ZZZ"]]);
+        let d = Network::new("L\n\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\nQQQ = (AAA, AAA)")
+            .unwrap()
+            .diagnostics();
+        assert_eq!(d.unreachable, ["QQQ"]);
+    }
+
+    #[test]
+    fn graphviz() {
+        let dot = Network::new(SAMPLE2).unwrap().graphviz();
+        assert_eq!(
+            dot,
+            "digraph network {
+\t\"AAA\" [style=filled, fillcolor=palegreen];
+\t\"ZZZ\" [style=filled, fillcolor=lightcoral];
+\t\"AAA\" -> \"BBB\" [label=\"LR\"];
+\t\"BBB\" -> \"AAA\" [label=\"L\"];
+\t\"BBB\" -> \"ZZZ\" [label=\"R\"];
+\t\"ZZZ\" -> \"ZZZ\" [label=\"LR\"];
+}
+"
+        );
     }
 }
@@ -1,22 +1,169 @@
+use std::{error::Error, fmt, num::ParseIntError};
+
+use num::{BigInt, BigRational, One, ToPrimitive, Zero};
+
 pub const PUZZLE: &str = include_str!("../../puzzles/day09.txt");
 
-fn main() {
-    let mut histories = parse(PUZZLE);
-    println!(
-        "Part 1: {}",
-        histories.iter().map(|v| predict(v)).sum::<isize>()
-    );
+fn main() -> Result<(), Box<dyn Error>> {
+    let histories = parse(PUZZLE)?;
+    println!("Part 1: {}", part1(&histories)?);
+    println!("Part 2: {}", part2(&histories)?);
+    Ok(())
+}
+
+pub fn part1(histories: &[Vec<isize>]) -> Result<isize, SequenceError> {
+    sum(histories, |s| s.forward(1))
+}
+
+pub fn part2(histories: &[Vec<isize>]) -> Result<isize, SequenceError> {
+    sum(histories, |s| s.backward(1))
+}
 
-    // This is a clear idiom for mutating all elements of an array.
-    // https://stackoverflow.com/a/28651397/5459668
-    for history in &mut histories {
-        history.reverse();
+fn sum(
+    histories: &[Vec<isize>],
+    f: impl Fn(&Sequence) -> Result<isize, SequenceError>,
+) -> Result<isize, SequenceError> {
+    histories.iter().try_fold(0isize, |total, history| {
+        total
+            .checked_add(f(&Sequence::new(history)?)?)
+            .ok_or(SequenceError::Overflow)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceError {
+    Empty,
+    /// We ran out of values before the differences were constant, so there's
+    /// no telling what comes next.
+    NeverConstant,
+    /// The answer doesn't fit in an `isize`.
+    Overflow,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceError::Empty => write!(f, "the history is empty"),
+            SequenceError::NeverConstant => {
+                write!(f, "the differences never settle on a constant")
+            }
+            SequenceError::Overflow => write!(f, "the answer doesn't fit in an isize"),
+        }
     }
+}
 
-    println!(
-        "Part 2: {}",
-        histories.iter().map(|v| predict(v)).sum::<isize>()
-    );
+impl Error for SequenceError {}
+
+/// A history as the polynomial that produced it. Position 0 is the first
+/// value in the history. All the arithmetic is on big integers or exact
+/// rationals, so only the final conversion back to `isize` can overflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence {
+    len: usize,
+    /// The first value in each row of differences, from the history itself
+    /// down to the first constant row. These are the coefficients of the
+    /// polynomial in Newton's forward difference form.
+    leading: Vec<BigInt>,
+}
+
+impl Sequence {
+    /// A row only counts as constant if it has at least two values to
+    /// compare.
+    pub fn new(history: &[isize]) -> Result<Self, SequenceError> {
+        if history.is_empty() {
+            return Err(SequenceError::Empty);
+        }
+        let mut row: Vec<BigInt> = history.iter().map(|&v| v.into()).collect();
+        let mut leading = vec![];
+        while row.len() >= 2 {
+            leading.push(row[0].clone());
+            if row.iter().all(|v| *v == row[0]) {
+                return Ok(Self {
+                    len: history.len(),
+                    leading,
+                });
+            }
+            row = row.windows(2).map(|w| &w[1] - &w[0]).collect();
+        }
+        Err(SequenceError::NeverConstant)
+    }
+
+    /// The value `k` steps after the end of the history.
+    pub fn forward(&self, k: usize) -> Result<isize, SequenceError> {
+        let x = (self.len - 1)
+            .checked_add(k)
+            .ok_or(SequenceError::Overflow)?;
+        self.checked(self.newton(BigInt::from(x)))
+    }
+
+    /// The value `k` steps before the start of the history.
+    pub fn backward(&self, k: usize) -> Result<isize, SequenceError> {
+        self.checked(self.newton(-BigInt::from(k)))
+    }
+
+    fn checked(&self, value: BigInt) -> Result<isize, SequenceError> {
+        value.to_isize().ok_or(SequenceError::Overflow)
+    }
+
+    /// Σ Δʲf(0) · C(x, j). For whole numbers x, every term is a whole
+    /// number, and so is every step of building the binomial coefficients.
+    fn newton<T>(&self, x: T) -> T
+    where
+        T: Clone + Zero + One + From<BigInt>,
+        for<'a> &'a T: std::ops::Sub<T, Output = T>
+            + std::ops::Mul<&'a T, Output = T>
+            + std::ops::Div<T, Output = T>,
+    {
+        let mut value = T::zero();
+        let mut binomial = T::one();
+        for (j, leading) in self.leading.iter().enumerate() {
+            value = value + &binomial * &T::from(leading.clone());
+            let j = T::from(BigInt::from(j));
+            binomial = &(&binomial * &(&x - j.clone())) / (j + T::one());
+        }
+        value
+    }
+}
+
+/// Everything else we can ask of the polynomial. The puzzle only needs one
+/// step in each direction.
+#[allow(dead_code)]
+impl Sequence {
+    pub fn degree(&self) -> usize {
+        self.leading.len() - 1
+    }
+
+    pub fn value_at(&self, x: isize) -> Result<isize, SequenceError> {
+        self.checked(self.newton(BigInt::from(x)))
+    }
+
+    /// The polynomial anywhere at all, even between positions.
+    pub fn interpolate(&self, x: &BigRational) -> BigRational {
+        self.newton(x.clone())
+    }
+
+    /// The coefficients of the polynomial, constant term first.
+    pub fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients = vec![BigRational::zero(); self.leading.len()];
+        // C(x, j) as a polynomial, constant term first.
+        let mut binomial = vec![BigRational::one()];
+        for (j, leading) in self.leading.iter().enumerate() {
+            let leading = BigRational::from(leading.clone());
+            for (c, b) in coefficients.iter_mut().zip(&binomial) {
+                *c += &leading * b;
+            }
+            // Multiply by (x - j) / (j + 1).
+            let j = BigRational::from(BigInt::from(j));
+            let denominator = &j + BigRational::one();
+            let mut next = vec![BigRational::zero(); binomial.len() + 1];
+            for (i, b) in binomial.iter().enumerate() {
+                next[i + 1] += b / &denominator;
+                next[i] -= b * &j / &denominator;
+            }
+            binomial = next;
+        }
+        coefficients
+    }
 }
 
 /// This is probably more clever than it should be.
@@ -36,6 +183,9 @@ fn main() {
 /// predicts the new term in the original sequence by adding.
 ///
 /// It's a neat take on differentiation.
+///
+/// [`Sequence`] does this properly now, but this is still a good check on it.
+#[allow(dead_code)]
 pub fn predict(history: &[isize]) -> isize {
     let n = history.len();
     let mut v = history.to_vec();
@@ -47,12 +197,12 @@ pub fn predict(history: &[isize]) -> isize {
     v.iter().sum()
 }
 
-pub fn parse(input: &str) -> Vec<Vec<isize>> {
+pub fn parse(input: &str) -> Result<Vec<Vec<isize>>, ParseIntError> {
     input
         .lines()
         .map(|line| {
             line.split_ascii_whitespace()
-                .flat_map(str::parse::<isize>)
+                .map(str::parse::<isize>)
                 .collect()
         })
         .collect()
@@ -68,7 +218,7 @@ mod day09 {
 
     #[test]
     fn test1() {
-        let histories = parse(SAMPLE);
+        let histories = parse(SAMPLE).unwrap();
         assert_eq!(predict(&histories[0]), 18);
         assert_eq!(predict(&histories[1]), 28);
         assert_eq!(predict(&histories[2]), 68);
@@ -76,7 +226,7 @@ mod day09 {
 
     #[test]
     fn test2() {
-        let mut histories = parse(SAMPLE);
+        let mut histories = parse(SAMPLE).unwrap();
         histories[0].reverse();
         histories[1].reverse();
         histories[2].reverse();
@@ -84,4 +234,69 @@ mod day09 {
         assert_eq!(predict(&histories[1]), 0);
         assert_eq!(predict(&histories[2]), 5);
     }
+
+    #[test]
+    fn sequences() {
+        let histories = parse(SAMPLE).unwrap();
+        assert_eq!(part1(&histories), Ok(114));
+        assert_eq!(part2(&histories), Ok(2));
+        let degrees: Vec<_> = histories
+            .iter()
+            .map(|h| Sequence::new(h).unwrap().degree())
+            .collect();
+        assert_eq!(degrees, [1, 2, 3]);
+
+        let s = Sequence::new(&histories[2]).unwrap();
+        assert_eq!(s.forward(1), Ok(68));
+        assert_eq!(s.forward(2), Ok(101));
+        assert_eq!(s.backward(1), Ok(5));
+        assert_eq!(s.backward(2), Ok(-4));
+        assert_eq!(s.value_at(3), Ok(21));
+        for history in &histories {
+            let s = Sequence::new(history).unwrap();
+            assert_eq!(s.forward(1).unwrap(), predict(history));
+            let mut reversed = history.clone();
+            reversed.reverse();
+            assert_eq!(s.backward(1).unwrap(), predict(&reversed));
+        }
+    }
+
+    #[test]
+    fn polynomials() {
+        // 1 3 6 10 15 21 are the triangular numbers, (x + 1)(x + 2) / 2.
+        let s = Sequence::new(&[1, 3, 6, 10, 15, 21]).unwrap();
+        let r = |n: i64, d: i64| BigRational::new(n.into(), d.into());
+        assert_eq!(s.coefficients(), [r(1, 1), r(3, 2), r(1, 2)]);
+        // Halfway between 1 and 3.
+        assert_eq!(s.interpolate(&r(1, 2)), r(15, 8));
+        assert_eq!(s.interpolate(&r(-3, 2)), r(-1, 8));
+        let s = Sequence::new(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(s.interpolate(&r(-1, 1)), r(5, 1));
+        assert_eq!(s.coefficients(), [r(10, 1), r(11, 3), r(-1, 1), r(1, 3)]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Sequence::new(&[]), Err(SequenceError::Empty));
+        assert_eq!(Sequence::new(&[7]), Err(SequenceError::NeverConstant));
+        assert_eq!(
+            Sequence::new(&[1, 2, 4, 8, 16]),
+            Err(SequenceError::NeverConstant)
+        );
+        assert!(Sequence::new(&[1, 2, 4, 8, 16, 31]).is_ok());
+
+        // Two values aren't enough to know that the differences are constant.
+        assert_eq!(Sequence::new(&[0, 1]), Err(SequenceError::NeverConstant));
+        let quarter = isize::MAX / 4;
+        let s = Sequence::new(&[0, quarter, 2 * quarter]).unwrap();
+        assert_eq!(s.forward(2), Ok(4 * quarter));
+        assert_eq!(s.forward(3), Err(SequenceError::Overflow));
+        assert_eq!(s.backward(4), Ok(-4 * quarter));
+        assert_eq!(s.backward(5), Err(SequenceError::Overflow));
+        assert_eq!(
+            part1(&[vec![isize::MAX, isize::MAX], vec![1, 1]]),
+            Err(SequenceError::Overflow)
+        );
+        assert!(parse("1 2 99999999999999999999").is_err());
+    }
 }
@@ -5,6 +5,7 @@ use std::{
     panic,
 };
 
+use crossterm::style::Stylize;
 use num::Complex;
 
 pub const PUZZLE: &str = include_str!("../../puzzles/day10.txt");
@@ -27,12 +28,68 @@ pub const PUZZLE: &str = include_str!("../../puzzles/day10.txt");
 ///   to count the integer coordinates inside the pipe, and
 /// - [Shoelace formula](https://www.reddit.com/r/adventofcode/comments/18f1sgh/comment/kcugm6t/)
 ///   for the area.
+///
+/// I came back and did those last two, see [`Algorithm`]. `--verify` runs
+/// all of them and complains if they disagree, and `--draw` shows the maze.
+/// `--analyse` looks at every pipe, not just the ones in the loop through S.
 fn main() {
     let pipes = Pipes::new(PUZZLE);
-    let (part1, part2) = pipes.solve();
+    if std::env::args().any(|arg| arg == "--analyse") {
+        for analysis in pipes.analyse() {
+            println!("{analysis}");
+        }
+        return;
+    }
+    if std::env::args().any(|arg| arg == "--verify") {
+        match pipes.verify() {
+            Ok((part1, part2)) => println!("All agree: {part1}, {part2}"),
+            Err(answers) => {
+                for (algorithm, answer) in answers {
+                    match answer {
+                        Some((part1, part2)) => println!("{algorithm:?}: {part1}, {part2}"),
+                        None => println!("{algorithm:?}: no loop"),
+                    }
+                }
+            }
+        }
+        return;
+    }
+    if std::env::args().any(|arg| arg == "--draw") {
+        print!("{}", pipes.render(Style::Ansi));
+    }
+    let Some((part1, part2)) = pipes.solve() else {
+        match pipes.shapes() {
+            [] => println!("No loop goes through S"),
+            shapes => println!("S could be any of {shapes:?}, see --analyse"),
+        }
+        return;
+    };
     println!("Part 1: {part1}");
     println!("Part 2: {part2}");
-    // println!("{pipes}");
+}
+
+/// Ways of counting the tiles inside the loop. They all find the loop the
+/// same way, so part 1 never changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Mark the tiles to the left and right of the path, then flood fill
+    /// whichever side turns out to be inside.
+    Sides,
+    /// The shoelace formula gives the area of the polygon through the
+    /// middle of each pipe. Pick's theorem, A = i + b/2 - 1, turns that into
+    /// the number of tiles strictly inside it.
+    Pick,
+    /// Scan each row from the left. Every pipe that goes up from its tile
+    /// takes us across the loop; tiles off the path after an odd number of
+    /// crossings are inside.
+    Scanline,
+}
+
+/// Parts 1 and 2.
+pub type Answer = (usize, usize);
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [Algorithm::Sides, Algorithm::Pick, Algorithm::Scanline];
 }
 
 pub struct Pipes {
@@ -40,6 +97,8 @@ pub struct Pipes {
     rows: i64,
     cols: i64,
     start: Complex<i64>,
+    /// Every shape of pipe under S that closes a loop through it.
+    shapes: Vec<char>,
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
@@ -104,69 +163,96 @@ impl Pipes {
             }
         }
 
-        Self {
+        let mut pipes = Self {
             area,
             rows: rows as i64,
             cols: cols as i64,
             start: start.unwrap(),
-        }
+            shapes: vec![],
+        };
+        pipes.shapes = "|-LJ7F".chars().filter(|&s| pipes.closes(s)).collect();
+        pipes
     }
 
-    fn get(&self, position: Complex<i64>) -> char {
-        match self.area.get(&position) {
-            Some('S') => self.s(),
-            Some(c) => *c,
-            None => '.',
+    /// The shape of the pipe under S, if exactly one shape closes a loop.
+    /// If there's a choice, [`Pipes::analyse`] shows each of them.
+    pub fn s(&self) -> Option<char> {
+        match self.shapes[..] {
+            [s] => Some(s),
+            _ => None,
         }
     }
 
-    fn initial_directions(&self) -> [Direction; 2] {
-        //let s = self.initial_position().expect("initial position");
-        let s = self.start;
-
-        let mut d = vec![];
-
-        if matches!(self.get(s + Left), '-' | 'L' | 'F') {
-            d.push(Left);
+    /// Follow the pipes from S, as the shape `s`, and see if we get back.
+    /// Pipes never branch, so there's only one way to go.
+    fn closes(&self, s: char) -> bool {
+        let links = self.links(self.start, Some(s));
+        if links.len() != 2 {
+            return false;
         }
-
-        if matches!(self.get(s + Right), '-' | 'J' | '7') {
-            d.push(Right);
+        let (mut previous, mut p) = (self.start, links[0]);
+        while p != self.start {
+            match self.links(p, Some(s)).into_iter().find(|&q| q != previous) {
+                Some(next) => (previous, p) = (p, next),
+                None => return false,
+            }
         }
+        true
+    }
 
-        if matches!(self.get(s + Down), '|' | 'L' | 'J') {
-            d.push(Down);
+    fn get(&self, position: Complex<i64>) -> char {
+        match self.area.get(&position) {
+            Some('S') => self.s().unwrap_or('.'),
+            Some(c) => *c,
+            None => '.',
         }
+    }
 
-        if matches!(self.get(s + Up), '|' | '7' | 'F') {
-            d.push(Up);
-        }
+    fn initial_directions(&self) -> Option<[Direction; 2]> {
+        self.ends(self.start, self.s()).try_into().ok()
+    }
 
-        d.try_into().expect("two initial directions")
+    /// The tiles a pipe is connected to. Both pipes have to point at each
+    /// other.
+    fn links(&self, position: Complex<i64>, s: Option<char>) -> Vec<Complex<i64>> {
+        self.ends(position, s)
+            .into_iter()
+            .map(|d| position + d)
+            .filter(|&p| self.ends(p, s).iter().any(|&d| p + d == position))
+            .collect()
     }
 
-    fn s(&self) -> char {
-        match self.initial_directions() {
-            [Left, Right] | [Right, Left] => '-',
-            [Left, Down] | [Down, Left] => '7',
-            [Left, Up] | [Up, Left] => 'J',
-            [Right, Down] | [Down, Right] => 'F',
-            [Right, Up] | [Up, Right] => 'L',
-            [Down, Up] | [Up, Down] => '|',
-            [x, y] if x == y => unreachable!(),
-            _ => panic!(),
+    fn ends(&self, position: Complex<i64>, s: Option<char>) -> Vec<Direction> {
+        let c = match self.area.get(&position) {
+            Some('S') => s.unwrap_or('.'),
+            Some(c) => *c,
+            None => '.',
+        };
+        match c {
+            '|' => vec![Up, Down],
+            '-' => vec![Left, Right],
+            'L' => vec![Up, Right],
+            'J' => vec![Up, Left],
+            '7' => vec![Down, Left],
+            'F' => vec![Down, Right],
+            _ => vec![],
         }
     }
 
     fn path(&self) -> HashMap<Complex<i64>, Direction> {
-        let mut path = HashMap::new();
-        //let s = self.initial_position().expect("initial position");
+        self.walk().unwrap_or_default().into_iter().collect()
+    }
+
+    /// The loop in order, starting from S, with the direction we leave each
+    /// tile in. `None` if there's no loop through S.
+    fn walk(&self) -> Option<Vec<(Complex<i64>, Direction)>> {
+        let mut path = vec![];
         let s = self.start;
         let mut p = s;
-        let mut d = self.initial_directions()[0];
+        let mut d = self.initial_directions()?[0];
 
         loop {
-            path.insert(p, d);
+            path.push((p, d));
             p = p + d;
             if p == s {
                 break;
@@ -180,7 +266,7 @@ impl Pipes {
                 _ => panic!("unexpected direction/pipe combination"),
             };
         }
-        path
+        Some(path)
     }
 
     fn sides(&self, path: &HashMap<Complex<i64>, Direction>) -> HashMap<Complex<i64>, Side> {
@@ -279,52 +365,278 @@ impl Pipes {
         }
     }
 
-    pub fn solve(&self) -> (usize, usize) {
-        let path = self.path();
-        let sides = self.sides(&path);
-        (
-            path.len() / 2,
-            sides.values().filter(|s| **s == Side::Inside).count(),
-        )
+    /// `None` unless exactly one shape of S closes a loop. See
+    /// [`Pipes::analyse`].
+    pub fn solve(&self) -> Option<Answer> {
+        self.solve_with(Algorithm::Sides)
+    }
+
+    pub fn solve_with(&self, algorithm: Algorithm) -> Option<Answer> {
+        let walk = self.walk()?;
+        let inside = match algorithm {
+            Algorithm::Sides => {
+                let sides = self.sides(&walk.iter().copied().collect());
+                sides.values().filter(|s| **s == Side::Inside).count()
+            }
+            Algorithm::Pick => Self::pick(&walk.iter().map(|(p, _)| *p).collect::<Vec<_>>()),
+            Algorithm::Scanline => self.scanline(&walk.iter().copied().collect()),
+        };
+        Some((walk.len() / 2, inside))
+    }
+
+    fn pick(tiles: &[Complex<i64>]) -> usize {
+        let twice_area: i64 = tiles
+            .iter()
+            .zip(tiles.iter().cycle().skip(1))
+            .map(|(a, b)| a.re * b.im - b.re * a.im)
+            .sum();
+        // i = A - b/2 + 1, doubled to stay in whole numbers.
+        let boundary = tiles.len() as i64;
+        ((twice_area.abs() - boundary + 2) / 2) as usize
+    }
+
+    fn scanline(&self, path: &HashMap<Complex<i64>, Direction>) -> usize {
+        self.inside(path).len()
+    }
+
+    fn inside(&self, path: &HashMap<Complex<i64>, Direction>) -> HashSet<Complex<i64>> {
+        let mut inside = HashSet::new();
+        for y in 1..=self.rows {
+            let mut crossings = 0;
+            for x in 0..self.cols {
+                let position = Complex::new(x, y);
+                if path.contains_key(&position) {
+                    // Counting only the pipes that go up means that a
+                    // stretch like L--7 counts once and L--J counts twice.
+                    if matches!(self.get(position), '|' | 'L' | 'J') {
+                        crossings += 1;
+                    }
+                } else if crossings % 2 == 1 {
+                    inside.insert(position);
+                }
+            }
+        }
+        inside
+    }
+
+    /// Every [`Algorithm`] should get the same answer. If they don't, here's
+    /// what each of them said.
+    #[allow(dead_code)]
+    pub fn verify(&self) -> Result<Answer, Vec<(Algorithm, Option<Answer>)>> {
+        let answers: Vec<_> = Algorithm::ALL
+            .into_iter()
+            .map(|algorithm| (algorithm, self.solve_with(algorithm)))
+            .collect();
+        match answers[0].1 {
+            Some(answer) if answers.iter().all(|(_, a)| *a == Some(answer)) => Ok(answer),
+            _ => Err(answers),
+        }
     }
 }
 
-impl fmt::Display for Pipes {
+/// A closed loop of pipes, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub tiles: Vec<Complex<i64>>,
+}
+
+impl Loop {
+    pub fn length(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// How many tiles the loop encloses. That includes tiles belonging to
+    /// any other loops nested inside it.
+    pub fn area(&self) -> usize {
+        Pipes::pick(&self.tiles)
+    }
+
+    /// How far along the loop we can get from a tile on it, and the tile(s)
+    /// that far away. An odd loop has two.
+    #[allow(dead_code)]
+    pub fn farthest(&self, from: Complex<i64>) -> Option<(usize, Vec<Complex<i64>>)> {
+        let i = self.tiles.iter().position(|&p| p == from)?;
+        let n = self.length();
+        let distance = n / 2;
+        let mut tiles = vec![self.tiles[(i + distance) % n]];
+        if n % 2 == 1 {
+            tiles.push(self.tiles[(i + distance + 1) % n]);
+        }
+        Some((distance, tiles))
+    }
+}
+
+/// Every pipe in the maze, with S as one particular shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// `None` if no shape of S closes a loop, in which case we treat it as
+    /// ground.
+    pub s: Option<char>,
+    pub loops: Vec<Loop>,
+    /// Pipes that don't make it all the way around, one component at a time.
+    pub dangling: Vec<Vec<Complex<i64>>>,
+}
+
+impl fmt::Display for Analysis {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.s {
+            Some(s) => writeln!(f, "With S as {s}:")?,
+            None => writeln!(f, "S doesn't close any loop:")?,
+        }
+        for l in &self.loops {
+            writeln!(
+                f,
+                "  loop of {} tiles around {} (farthest {})",
+                l.length(),
+                l.area(),
+                l.length() / 2
+            )?;
+        }
+        write!(
+            f,
+            "  {} dangling components, {} tiles",
+            self.dangling.len(),
+            self.dangling.iter().map(Vec::len).sum::<usize>()
+        )
+    }
+}
+
+// Only `--analyse` and the tests look at pipes outside the loop through S.
+#[allow(dead_code)]
+impl Pipes {
+    /// Every shape of pipe under S that closes a loop through it.
+    pub fn shapes(&self) -> &[char] {
+        &self.shapes
+    }
+
+    /// The position of a tile, counting rows from the top like the input does.
+    pub fn at(&self, row: usize, col: usize) -> Complex<i64> {
+        Complex::new(col as i64, self.rows - row as i64)
+    }
+
+    /// Try S as every shape of pipe. Any shape that closes a loop through S
+    /// gets an analysis. S could be the missing link of two different loops,
+    /// so there can be more than one.
+    pub fn analyse(&self) -> Vec<Analysis> {
+        let analyses: Vec<_> = "|-LJ7F"
+            .chars()
+            .map(|s| self.analyse_with(Some(s)))
+            .filter(|a| a.loops.iter().any(|l| l.tiles.contains(&self.start)))
+            .collect();
+        if analyses.is_empty() {
+            vec![self.analyse_with(None)]
+        } else {
+            analyses
+        }
+    }
+
+    fn analyse_with(&self, s: Option<char>) -> Analysis {
+        let mut explored = HashSet::new();
+        let mut loops = vec![];
+        let mut dangling = vec![];
+        for row in 0..self.rows as usize {
+            for col in 0..self.cols as usize {
+                let position = self.at(row, col);
+                if explored.contains(&position) || self.ends(position, s).is_empty() {
+                    continue;
+                }
+                let component = self.component(position, s);
+                explored.extend(component.iter().copied());
+                if component.iter().all(|&p| self.links(p, s).len() == 2) {
+                    loops.push(Loop { tiles: component });
+                } else {
+                    dangling.push(component);
+                }
+            }
+        }
+        Analysis { s, loops, dangling }
+    }
+
+    /// Every tile connected to this one. Pipes never branch, so a component
+    /// is either a loop or a line. Either way, we list the tiles in order by
+    /// going to one end first and walking back from there.
+    fn component(&self, position: Complex<i64>, s: Option<char>) -> Vec<Complex<i64>> {
+        let mut end = position;
+        let mut previous = None;
+        while let Some(&next) = self.links(end, s).iter().find(|&&p| Some(p) != previous) {
+            previous = Some(end);
+            end = next;
+            if end == position {
+                break;
+            }
+        }
+        let mut tiles = vec![end];
+        let mut previous = None;
+        let mut p = end;
+        while let Some(&next) = self.links(p, s).iter().find(|&&q| Some(q) != previous) {
+            if next == end {
+                break;
+            }
+            tiles.push(next);
+            previous = Some(p);
+            p = next;
+        }
+        tiles
+    }
+}
+
+/// How to draw the maze. Both draw the loop with box-drawing characters.
+/// Plain text marks every other tile `I` or `O`, for inside or outside the
+/// loop. ANSI keeps the other tiles as they were and colours them instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Plain,
+    #[allow(dead_code)]
+    Ansi,
+}
+
+impl Pipes {
+    pub fn render(&self, style: Style) -> String {
         let path = self.path();
-        let sides = self.sides(&path);
-        for y in (0..self.rows).rev() {
+        let inside = self.inside(&path);
+        let mut out = String::new();
+        for y in (1..=self.rows).rev() {
             for x in 0..self.cols {
                 let position = Complex::new(x, y);
-                write!(
-                    f,
-                    "{}",
-                    match path.get(&position) {
-                        Some(Left) => "<",
-                        Some(Right) => ">",
-                        Some(Up) => "^",
-                        Some(Down) => "v",
-                        None => "",
+                let glyph = box_drawing(self.get(position));
+                let tile = match (
+                    style,
+                    path.contains_key(&position),
+                    inside.contains(&position),
+                ) {
+                    (Style::Plain, true, _) => glyph.to_string(),
+                    (Style::Plain, false, true) => "I".to_string(),
+                    (Style::Plain, false, false) => "O".to_string(),
+                    (Style::Ansi, true, _) if position == self.start => {
+                        glyph.red().bold().to_string()
                     }
-                )?;
-                write!(
-                    f,
-                    "{}",
-                    match sides.get(&position) {
-                        Some(Side::Left) => "1",
-                        Some(Side::Right) => "2",
-                        Some(Side::Inside) => "I",
-                        Some(Side::Outside) => "O",
-                        None => "",
-                    }
-                )?;
-                if !path.contains_key(&position) && !sides.contains_key(&position) {
-                    write!(f, " ")?;
-                }
+                    (Style::Ansi, true, _) => glyph.yellow().bold().to_string(),
+                    (Style::Ansi, false, true) => glyph.black().on_green().to_string(),
+                    (Style::Ansi, false, false) => glyph.dark_grey().to_string(),
+                };
+                out.push_str(&tile);
             }
-            writeln!(f)?;
+            out.push('\n');
         }
-        Ok(())
+        out
+    }
+}
+
+fn box_drawing(c: char) -> char {
+    match c {
+        '|' => '│',
+        '-' => '─',
+        'L' => '└',
+        'J' => '┘',
+        '7' => '┐',
+        'F' => '┌',
+        _ => c,
+    }
+}
+
+impl fmt::Display for Pipes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(Style::Plain))
     }
 }
 
@@ -369,30 +681,144 @@ L--J.L7...LJS7F-7L7.
 .L--JL--J.
 ..........";
 
+    const SAMPLE5: &str = "FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L";
+
     #[test]
     fn test1() {
         let pipes = Pipes::new(SAMPLE1);
-        assert_eq!(pipes.solve().0, 8);
+        assert_eq!(pipes.solve().unwrap().0, 8);
     }
 
     #[test]
     fn test2() {
         let pipes = Pipes::new(SAMPLE2);
         println!("{pipes}");
-        assert_eq!(pipes.solve().1, 4);
+        assert_eq!(pipes.solve().unwrap().1, 4);
     }
 
     #[test]
     fn test3() {
         let pipes = Pipes::new(SAMPLE3);
         println!("{pipes}");
-        assert_eq!(pipes.solve().1, 8);
+        assert_eq!(pipes.solve().unwrap().1, 8);
     }
 
     #[test]
     fn test4() {
         let pipes = Pipes::new(SAMPLE4);
         println!("{pipes}");
-        assert_eq!(pipes.solve().1, 4);
+        assert_eq!(pipes.solve().unwrap().1, 4);
+    }
+
+    #[test]
+    fn algorithms() {
+        for (sample, expected) in [
+            (SAMPLE1, (8, 1)),
+            (SAMPLE2, (23, 4)),
+            (SAMPLE3, (70, 8)),
+            (SAMPLE4, (22, 4)),
+            (SAMPLE5, (80, 10)),
+        ] {
+            assert_eq!(Pipes::new(sample).verify(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn render() {
+        let pipes = Pipes::new(SAMPLE1);
+        assert_eq!(
+            pipes.render(Style::Plain),
+            "\
+OO┌┐O
+O┌┘│O
+┌┘I└┐
+│┌──┘
+└┘OOO
+"
+        );
+        assert_eq!(
+            Pipes::new(SAMPLE3).to_string(),
+            include_str!("../../golden/day10-render.txt")
+        );
+        let ansi = pipes.render(Style::Ansi);
+        assert!(ansi.contains("\x1b["));
+        assert_eq!(ansi.matches('┌').count(), 4);
+    }
+
+    #[test]
+    fn analysis() {
+        // S could join either loop, but not both.
+        let pipes = Pipes::new(
+            "\
+F-7....
+|.|.F7.
+L-S-JL7
+..|.-.|
+..L---J",
+        );
+        let analyses = pipes.analyse();
+        assert_eq!(analyses.len(), 2);
+        assert_eq!(analyses[0].s, Some('J'));
+        assert_eq!(analyses[1].s, Some('F'));
+        // Solving won't pick one for us.
+        assert_eq!(pipes.shapes(), ['J', 'F']);
+        assert_eq!(pipes.s(), None);
+        assert_eq!(pipes.solve(), None);
+
+        let upper = &analyses[0].loops[0];
+        assert_eq!((upper.length(), upper.area()), (8, 1));
+        assert_eq!(analyses[0].loops.len(), 1);
+        // What would have been the other loop, and the stray - inside it.
+        assert_eq!(analyses[0].dangling.len(), 2);
+        assert_eq!(upper.farthest(pipes.start), Some((4, vec![pipes.at(0, 0)])));
+        assert_eq!(upper.farthest(pipes.at(4, 4)), None);
+
+        let lower = &analyses[1].loops[0];
+        assert_eq!((lower.length(), lower.area()), (14, 3));
+        assert_eq!(lower.farthest(pipes.start).unwrap().0, 7);
+
+        // A loop with an odd number of tiles can't be drawn with pipes, but
+        // its farthest tiles still make sense.
+        let triangle = Loop {
+            tiles: vec![pipes.at(0, 0), pipes.at(0, 1), pipes.at(1, 0)],
+        };
+        assert_eq!(
+            triangle.farthest(pipes.at(0, 0)),
+            Some((1, vec![pipes.at(0, 1), pipes.at(1, 0)]))
+        );
+    }
+
+    #[test]
+    fn analysis_agrees() {
+        for sample in [SAMPLE1, SAMPLE2, SAMPLE3, SAMPLE4, SAMPLE5] {
+            let pipes = Pipes::new(sample);
+            let analyses = pipes.analyse();
+            assert_eq!(analyses.len(), 1);
+            assert_eq!(analyses[0].s, pipes.s());
+            let main = analyses[0]
+                .loops
+                .iter()
+                .find(|l| l.tiles.contains(&pipes.start))
+                .unwrap();
+            assert_eq!(Some((main.length() / 2, main.area())), pipes.solve());
+        }
+        // S in the middle of nowhere.
+        let pipes = Pipes::new("F7.\nLJS");
+        let analyses = pipes.analyse();
+        assert_eq!(analyses[0].s, None);
+        assert_eq!(analyses[0].loops.len(), 1);
+        assert_eq!(analyses[0].dangling.len(), 0);
+        assert_eq!(pipes.solve(), None);
+        assert!(pipes.verify().is_err());
+        assert_eq!(pipes.to_string(), "OOO\nOOO\n");
     }
 }
@@ -1,5 +1,3 @@
-use std::collections::HashSet;
-
 pub const PUZZLE: &str = include_str!("../../puzzles/day11.txt");
 
 fn main() {
@@ -36,11 +34,89 @@ impl Image {
     }
 
     pub fn predict(&self, expansion: usize) -> usize {
-        let occupied_rows: HashSet<usize> = self.galaxies.iter().map(|p| p.y).collect();
-        let occupied_cols: HashSet<usize> = self.galaxies.iter().map(|p| p.x).collect();
+        self.expand(expansion, expansion).total_distance()
+    }
+
+    /// Move the galaxies apart. Every empty row becomes `rows` rows, and
+    /// every empty column becomes `cols` columns.
+    ///
+    /// A coordinate has as many empty lines before it as it has lines, less
+    /// the occupied ones, and we can count those by binary search.
+    pub fn expand(&self, rows: usize, cols: usize) -> Expanded {
+        let ys = Self::expand_axis(self.galaxies.iter().map(|p| p.y), rows);
+        let xs = Self::expand_axis(self.galaxies.iter().map(|p| p.x), cols);
+        Expanded {
+            galaxies: xs
+                .into_iter()
+                .zip(ys)
+                .map(|(x, y)| Position { x, y })
+                .collect(),
+        }
+    }
+
+    fn expand_axis(coordinates: impl Iterator<Item = usize>, factor: usize) -> Vec<usize> {
+        let coordinates: Vec<_> = coordinates.collect();
+        let mut occupied = coordinates.clone();
+        occupied.sort_unstable();
+        occupied.dedup();
+        coordinates
+            .into_iter()
+            .map(|c| {
+                let empty = c - occupied.partition_point(|&o| o < c);
+                c + (factor - 1) * empty
+            })
+            .collect()
+    }
+}
+
+/// The galaxies after expansion, in the same order as in the image (left to
+/// right, top to bottom).
+pub struct Expanded {
+    galaxies: Vec<Position>,
+}
+
+impl Expanded {
+    /// Manhattan distances split into one sum per axis, and on a sorted axis
+    /// the i-th coordinate is the bigger one in exactly i pairs. So each axis
+    /// is a sort and a running total instead of a loop over every pair.
+    pub fn total_distance(&self) -> usize {
+        Self::axis_total(self.galaxies.iter().map(|p| p.x).collect())
+            + Self::axis_total(self.galaxies.iter().map(|p| p.y).collect())
+    }
+
+    fn axis_total(mut coordinates: Vec<usize>) -> usize {
+        coordinates.sort_unstable();
+        let mut before = 0;
         let mut total = 0;
-        for (i, p1) in self.galaxies.iter().enumerate() {
-            for p2 in self.galaxies[i + 1..].iter() {
+        for (i, c) in coordinates.into_iter().enumerate() {
+            total += i * c - before;
+            before += c;
+        }
+        total
+    }
+
+    /// The distance between two galaxies, numbered from 0.
+    #[allow(dead_code)]
+    pub fn distance(&self, a: usize, b: usize) -> Option<usize> {
+        let (a, b) = (self.galaxies.get(a)?, self.galaxies.get(b)?);
+        Some(a.x.abs_diff(b.x) + a.y.abs_diff(b.y))
+    }
+}
+
+#[cfg(test)]
+mod day11 {
+    use super::*;
+    use advent_of_code_2023::rng::Lcg;
+    use std::collections::HashSet;
+
+    /// The way we used to do it: every pair, and every row and column in
+    /// between.
+    fn oracle(image: &Image, rows: usize, cols: usize) -> usize {
+        let occupied_rows: HashSet<usize> = image.galaxies.iter().map(|p| p.y).collect();
+        let occupied_cols: HashSet<usize> = image.galaxies.iter().map(|p| p.x).collect();
+        let mut total = 0;
+        for (i, p1) in image.galaxies.iter().enumerate() {
+            for p2 in image.galaxies[i + 1..].iter() {
                 let dy = p2.y.abs_diff(p1.y);
                 let dx = p2.x.abs_diff(p1.x);
                 let y_empty = (p2.y.min(p1.y) + 1..p2.y.max(p1.y))
@@ -49,17 +125,11 @@ impl Image {
                 let x_empty = (p2.x.min(p1.x) + 1..p2.x.max(p1.x))
                     .filter(|x| !occupied_cols.contains(x))
                     .count();
-                let distance = dy + dx + (expansion - 1) * y_empty + (expansion - 1) * x_empty;
-                total += distance;
+                total += dy + dx + (rows - 1) * y_empty + (cols - 1) * x_empty;
             }
         }
         total
     }
-}
-
-#[cfg(test)]
-mod day11 {
-    use super::*;
 
     const SAMPLE: &str = "...#......
 .......#..
@@ -96,4 +166,41 @@ mod day11 {
     fn small() {
         assert_eq!(Image::new(SMALL).predict(2), 16)
     }
+
+    #[test]
+    fn pairs() {
+        let expanded = Image::new(SAMPLE).expand(2, 2);
+        assert_eq!(expanded.distance(4, 8), Some(9));
+        assert_eq!(expanded.distance(0, 6), Some(15));
+        assert_eq!(expanded.distance(2, 5), Some(17));
+        assert_eq!(expanded.distance(7, 8), Some(5));
+        assert_eq!(expanded.distance(8, 7), Some(5));
+        assert_eq!(expanded.distance(3, 3), Some(0));
+        assert_eq!(expanded.distance(0, 9), None);
+    }
+
+    #[test]
+    fn against_oracle() {
+        let mut rng = Lcg::new(11);
+        for _ in 0..100 {
+            let mut input = String::new();
+            for _ in 0..12 {
+                for _ in 0..15 {
+                    input.push(if rng.next_u32().is_multiple_of(8) {
+                        '#'
+                    } else {
+                        '.'
+                    });
+                }
+                input.push('\n');
+            }
+            let image = Image::new(&input);
+            for (rows, cols) in [(1, 1), (2, 2), (1, 7), (10, 3), (1_000_000, 1)] {
+                assert_eq!(
+                    image.expand(rows, cols).total_distance(),
+                    oracle(&image, rows, cols)
+                );
+            }
+        }
+    }
 }
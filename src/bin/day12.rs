@@ -1,4 +1,14 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    iter::{self, Sum},
+    ops::{Add, AddAssign, Mul, SubAssign},
+    str::FromStr,
+};
+
+use num::{BigUint, One, Zero};
+use rayon::prelude::*;
 
 pub const PUZZLE: &str = include_str!("../../puzzles/day12.txt");
 
@@ -31,13 +41,41 @@ pub const PUZZLE: &str = include_str!("../../puzzles/day12.txt");
 /// The [memorize](https://docs.rs/memorize/latest/memorize/)
 /// crate might have been usable for this task, but probably
 /// not when using the object-oriented approach.
-pub fn main() {
+///
+/// These days the counting is done by filling in a [`Table`] instead,
+/// which has no recursion to overflow the stack on long rows. `matches` is
+/// still here to check it against. Give an unfold factor as an argument to
+/// use something other than 5; the counts are big integers for that.
+///
+/// `--nonogram <file>` solves a [`Nonogram`] instead.
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [flag, path] = &args[..]
+        && flag == "--nonogram"
+    {
+        let nonogram: Nonogram = std::fs::read_to_string(path)?.parse()?;
+        println!("{}", nonogram.solve());
+        return Ok(());
+    }
+    let factor = args.first().map_or(Ok(5), |f| f.parse())?;
     let mut springs = Springs::new(PUZZLE);
     println!("Part 1: {}", springs.total_arrangements());
-    springs.unfold();
-    println!("Part 2: {}", springs.total_arrangements());
+    springs.unfold(factor);
+    println!("Part 2: {}", springs.total::<BigUint>());
+    Ok(())
 }
 
+/// Anything we can count arrangements with: `usize` for the puzzle,
+/// `BigUint` for rows unfolded so far that nothing else is big enough.
+pub trait Count: Clone + Zero + One + for<'a> AddAssign<&'a Self> {}
+
+impl<T: Clone + Zero + One + for<'a> AddAssign<&'a T>> Count for T {}
+
+/// A [`Count`] we can also pick out the `k`th arrangement with.
+pub trait Rank: Count + Ord + for<'a> SubAssign<&'a Self> {}
+
+impl<T: Count + Ord + for<'a> SubAssign<&'a T>> Rank for T {}
+
 #[derive(Debug)]
 pub struct Springs(Vec<SpringRow>);
 
@@ -47,12 +85,17 @@ impl Springs {
     }
 
     pub fn total_arrangements(&self) -> usize {
-        self.0.iter().map(SpringRow::arrangements).sum()
+        self.total()
     }
 
-    pub fn unfold(&mut self) {
+    /// The rows are independent, so we count them in parallel.
+    pub fn total<T: Count + Send + Sum>(&self) -> T {
+        self.0.par_iter().map(SpringRow::count).sum()
+    }
+
+    pub fn unfold(&mut self, factor: usize) {
         for row in &mut self.0 {
-            row.unfold();
+            row.unfold(factor);
         }
     }
 }
@@ -79,13 +122,13 @@ impl Symbol {
 }
 
 #[derive(Debug)]
-struct SpringRow {
+pub struct SpringRow {
     springs: Vec<Symbol>,
     damaged: Vec<usize>,
 }
 
 impl SpringRow {
-    fn new(input: &str) -> Self {
+    pub fn new(input: &str) -> Self {
         let mut row = input.trim().split_ascii_whitespace();
         let springs = row.next().expect("symbols of spring status");
         let springs = Symbol::new(springs);
@@ -99,21 +142,27 @@ impl SpringRow {
         Self { springs, damaged }
     }
 
-    fn unfold(&mut self) {
+    fn unfold(&mut self, factor: usize) {
         let mut v = Vec::new();
-        for i in 1..=5 {
+        for i in 1..=factor {
             for e in &self.springs {
                 v.push(e.clone());
             }
-            if i < 5 {
+            if i < factor {
                 v.push(Symbol::Unknown);
             }
         }
         self.springs = v;
-        self.damaged = self.damaged.repeat(5);
+        self.damaged = self.damaged.repeat(factor);
     }
 
-    fn arrangements(&self) -> usize {
+    pub fn count<T: Count>(&self) -> T {
+        Table::new(self).total()
+    }
+
+    /// The original recursive solution, to check the table against.
+    #[allow(dead_code)]
+    fn arrangements_recursive(&self) -> usize {
         matches(
             &self.springs[..],
             &self.damaged[..],
@@ -124,6 +173,356 @@ impl SpringRow {
     }
 }
 
+// Only the tests look at the arrangements themselves.
+#[allow(dead_code)]
+impl SpringRow {
+    /// Every arrangement, in lexicographic order. `#` sorts before `.` in
+    /// ASCII, so the arrangements with the leftmost damaged springs come
+    /// first. Each one is built on demand by [`SpringRow::nth`], counting
+    /// with big integers so that we never wrap around.
+    pub fn enumerate(&self) -> impl Iterator<Item = String> + '_ {
+        let table = Table::<BigUint>::new(self);
+        let mut k = BigUint::zero();
+        iter::from_fn(move || {
+            let arrangement = table.nth(self, k.clone())?;
+            k += 1u32;
+            Some(arrangement)
+        })
+    }
+
+    /// The `k`th arrangement in lexicographic order, counting from 0. The
+    /// counts are done in `T`, so it has to be big enough for all of them.
+    pub fn nth<T: Rank>(&self, k: T) -> Option<String> {
+        Table::new(self).nth(self, k)
+    }
+
+    /// An arrangement chosen uniformly at random. `random(n)` should give a
+    /// uniformly random number below `n`. Use `BigUint` for unfolded rows
+    /// with more arrangements than a `usize` can count.
+    pub fn sample<T: Rank>(&self, random: impl FnOnce(T) -> T) -> Option<String> {
+        let table = Table::<T>::new(self);
+        match table.total() {
+            n if n.is_zero() => None,
+            n => table.nth(self, random(n)),
+        }
+    }
+}
+
+/// How many ways there are to finish a row from each position, with some
+/// number of groups already placed, assuming we're free to start a group
+/// there.
+///
+/// With this we can walk straight to the `k`th arrangement. At each
+/// position, either the next group starts there (which sorts first, because
+/// it starts with `#`) or the spring is operational. If `k` is less than the
+/// number of ways to finish after starting the group, that's what we do.
+/// Otherwise we skip all of those and carry on with a `.`.
+struct Table<T = usize> {
+    ways: Vec<Vec<T>>,
+    /// How many springs in a row, from each position, could be damaged.
+    run: Vec<usize>,
+}
+
+impl<T: Count> Table<T> {
+    fn new(row: &SpringRow) -> Self {
+        let n = row.springs.len();
+        let groups = row.damaged.len();
+        let mut run = vec![0; n + 1];
+        for i in (0..n).rev() {
+            if row.springs[i] != Symbol::Operational {
+                run[i] = run[i + 1] + 1;
+            }
+        }
+        let mut table = Self {
+            ways: vec![vec![T::zero(); groups + 1]; n + 1],
+            run,
+        };
+        table.ways[n][groups] = T::one();
+        for i in (0..n).rev() {
+            for g in 0..=groups {
+                let mut ways = T::zero();
+                if row.springs[i] != Symbol::Damaged {
+                    ways += &table.ways[i + 1][g];
+                }
+                if let Some(next) = table.group(row, i, g) {
+                    ways += &table.ways[next][g + 1];
+                }
+                table.ways[i][g] = ways;
+            }
+        }
+        table
+    }
+
+    fn total(&self) -> T {
+        self.ways[0][0].clone()
+    }
+
+    /// If group `g` can start at position `i`, then where we carry on from
+    /// after it and the operational spring that has to follow it.
+    fn group(&self, row: &SpringRow, i: usize, g: usize) -> Option<usize> {
+        let n = row.springs.len();
+        let len = *row.damaged.get(g)?;
+        let end = i + len;
+        (self.run[i] >= len && (end == n || row.springs[end] != Symbol::Damaged))
+            .then_some((end + 1).min(n))
+    }
+}
+
+impl<T: Rank> Table<T> {
+    fn nth(&self, row: &SpringRow, mut k: T) -> Option<String> {
+        if k >= self.total() {
+            return None;
+        }
+        let n = row.springs.len();
+        let mut arrangement = String::with_capacity(n);
+        let (mut i, mut g) = (0, 0);
+        while i < n {
+            if let Some(next) = self.group(row, i, g) {
+                let ways = &self.ways[next][g + 1];
+                if k < *ways {
+                    arrangement.push_str(&"#".repeat(row.damaged[g]));
+                    if next > i + row.damaged[g] {
+                        arrangement.push('.');
+                    }
+                    i = next;
+                    g += 1;
+                    continue;
+                }
+                k -= ways;
+            }
+            arrangement.push('.');
+            i += 1;
+        }
+        Some(arrangement)
+    }
+}
+
+/// A picture puzzle where every row and every column is a row of springs:
+/// the clues are the sizes of the groups of filled (damaged) cells.
+///
+/// The input is the row clues, a blank line, then the column clues, one
+/// line each, like `3,1`. A line with nothing filled in is `0`.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct Nonogram {
+    rows: Vec<Vec<usize>>,
+    cols: Vec<Vec<usize>>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid(Vec<Vec<Symbol>>);
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Solution {
+    Unique(Grid),
+    /// Two of the solutions, to show that they're different.
+    Multiple(Grid, Grid),
+    None,
+}
+
+impl FromStr for Nonogram {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rows, cols) = s
+            .trim()
+            .split_once("\n\n")
+            .ok_or("expected row clues, a blank line, then column clues")?;
+        let clues = |block: &str, offset: usize| {
+            block
+                .lines()
+                .enumerate()
+                .map(|(n, line)| {
+                    line.trim()
+                        .split(',')
+                        .map(|c| c.trim().parse::<usize>())
+                        .filter(|c| *c != Ok(0))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| format!("line {}: {e}", n + offset + 1))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let rows = clues(rows, 0)?;
+        let cols = clues(cols, rows.len() + 1)?;
+        Ok(Self { rows, cols })
+    }
+}
+
+#[allow(dead_code)]
+impl Nonogram {
+    /// Fill in whatever the clues force, then guess the first cell we still
+    /// don't know and try again both ways. We stop as soon as we find a
+    /// second solution.
+    pub fn solve(&self) -> Solution {
+        let blank = Grid(vec![
+            vec![Symbol::Unknown; self.cols.len()];
+            self.rows.len()
+        ]);
+        let mut solutions = vec![];
+        self.search(blank, &mut solutions);
+        let mut solutions = solutions.into_iter();
+        match (solutions.next(), solutions.next()) {
+            (None, _) => Solution::None,
+            (Some(grid), None) => Solution::Unique(grid),
+            (Some(a), Some(b)) => Solution::Multiple(a, b),
+        }
+    }
+
+    fn search(&self, mut grid: Grid, solutions: &mut Vec<Grid>) {
+        if solutions.len() >= 2 || self.propagate(&mut grid).is_none() {
+            return;
+        }
+        let unknown = grid.0.iter().enumerate().find_map(|(r, row)| {
+            let c = row.iter().position(|s| *s == Symbol::Unknown)?;
+            Some((r, c))
+        });
+        match unknown {
+            None => solutions.push(grid),
+            Some((r, c)) => {
+                for guess in [Symbol::Damaged, Symbol::Operational] {
+                    let mut grid = grid.clone();
+                    grid.0[r][c] = guess;
+                    self.search(grid, solutions);
+                }
+            }
+        }
+    }
+
+    /// Solve every row and column on its own until nothing changes. `None`
+    /// means some line has no arrangements left.
+    fn propagate(&self, grid: &mut Grid) -> Option<()> {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (r, clue) in self.rows.iter().enumerate() {
+                let line = Self::line(&grid.0[r], clue)?;
+                changed |= line != grid.0[r];
+                grid.0[r] = line;
+            }
+            for (c, clue) in self.cols.iter().enumerate() {
+                let col: Vec<_> = grid.0.iter().map(|row| row[c].clone()).collect();
+                let line = Self::line(&col, clue)?;
+                if line != col {
+                    changed = true;
+                    for (row, s) in grid.0.iter_mut().zip(line) {
+                        row[c] = s;
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Try each unknown cell both ways, and see if there are any
+    /// arrangements. If one way has none, it's the other. This is slower than working out which
+    /// cells every arrangement agrees on, but it's the same engine as the
+    /// puzzle.
+    fn line(springs: &[Symbol], clue: &[usize]) -> Option<Vec<Symbol>> {
+        let possible = |springs: Vec<Symbol>| {
+            let row = SpringRow {
+                springs,
+                damaged: clue.to_vec(),
+            };
+            row.count::<Possible>().0
+        };
+        if !possible(springs.to_vec()) {
+            return None;
+        }
+        let mut line = springs.to_vec();
+        for (i, s) in springs.iter().enumerate() {
+            if *s != Symbol::Unknown {
+                continue;
+            }
+            let mut damaged = springs.to_vec();
+            damaged[i] = Symbol::Damaged;
+            if !possible(damaged) {
+                line[i] = Symbol::Operational;
+                continue;
+            }
+            let mut operational = springs.to_vec();
+            operational[i] = Symbol::Operational;
+            if !possible(operational) {
+                line[i] = Symbol::Damaged;
+            }
+        }
+        Some(line)
+    }
+}
+
+/// Whether there are any arrangements at all, as a [`Count`] that can't
+/// overflow however long the line is: adding is "or", and one is "yes".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Possible(bool);
+
+impl Add for Possible {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Possible(self.0 || other.0)
+    }
+}
+
+impl AddAssign<&Possible> for Possible {
+    fn add_assign(&mut self, other: &Self) {
+        *self = *self + *other;
+    }
+}
+
+impl Mul for Possible {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Possible(self.0 && other.0)
+    }
+}
+
+impl Zero for Possible {
+    fn zero() -> Self {
+        Possible(false)
+    }
+
+    fn is_zero(&self) -> bool {
+        !self.0
+    }
+}
+
+impl One for Possible {
+    fn one() -> Self {
+        Possible(true)
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.0 {
+            for s in row {
+                let c = match s {
+                    Symbol::Operational => '.',
+                    Symbol::Damaged => '#',
+                    Symbol::Unknown => '?',
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Solution::Unique(grid) => write!(f, "Unique solution:\n{grid}"),
+            Solution::Multiple(a, b) => {
+                write!(f, "More than one solution, including\n{a}\nand\n{b}")
+            }
+            Solution::None => write!(f, "No solution"),
+        }
+    }
+}
+
 fn matches(
     symbols: &[Symbol],
     group: &[usize],
@@ -201,6 +600,7 @@ enum State {
 #[cfg(test)]
 mod day12 {
     use super::*;
+    use advent_of_code_2023::rng::Lcg;
 
     const SAMPLE: &str = "???.### 1,1,3
 .??..??...?##. 1,1,3
@@ -225,7 +625,7 @@ mod day12 {
     #[test]
     fn unfolding() {
         let mut s = SpringRow::new(".# 1");
-        s.unfold();
+        s.unfold(5);
         let t = SpringRow::new(".#?.#?.#?.#?.# 1,1,1,1,1");
         assert_eq!(s.springs, t.springs);
         assert_eq!(s.damaged, t.damaged);
@@ -234,7 +634,234 @@ mod day12 {
     #[test]
     fn test2() {
         let mut s = Springs::new(SAMPLE);
-        s.unfold();
+        s.unfold(5);
         assert_eq!(s.total_arrangements(), 525152)
     }
+
+    /// The sizes of the groups of damaged springs.
+    fn groups(arrangement: &str) -> Vec<usize> {
+        arrangement
+            .split('.')
+            .filter(|g| !g.is_empty())
+            .map(str::len)
+            .collect()
+    }
+
+    fn fits(row: &str, arrangement: &str) -> bool {
+        let (springs, damaged) = row.split_once(' ').unwrap();
+        let damaged: Vec<usize> = damaged.split(',').map(|d| d.parse().unwrap()).collect();
+        springs.len() == arrangement.len()
+            && springs
+                .chars()
+                .zip(arrangement.chars())
+                .all(|(s, a)| s == '?' || s == a)
+            && groups(arrangement) == damaged
+    }
+
+    #[test]
+    fn enumeration() {
+        for line in SAMPLE.lines().chain(SMALL.lines()) {
+            let row = SpringRow::new(line);
+            let all: Vec<_> = row.enumerate().collect();
+            assert_eq!(all.len(), row.count::<usize>());
+            assert!(all.windows(2).all(|w| w[0] < w[1]));
+            assert!(all.iter().all(|a| fits(line, a)));
+            assert_eq!(row.nth(all.len()), None);
+        }
+        let row = SpringRow::new("?###???????? 3,2,1");
+        assert_eq!(row.nth(0usize).unwrap(), ".###.##.#...");
+        assert_eq!(row.nth(BigUint::from(9u32)).unwrap(), ".###....##.#");
+        assert_eq!(SpringRow::new("#.# 2").enumerate().count(), 0);
+        assert_eq!(
+            SpringRow::new("#.# 2").sample::<usize>(|_| unreachable!()),
+            None
+        );
+    }
+
+    #[test]
+    fn sampling() {
+        let mut rng = Lcg::new(12);
+        let mut random = |n: usize| rng.below(n);
+
+        let line = "?###???????? 3,2,1";
+        let row = SpringRow::new(line);
+        let mut seen = HashMap::new();
+        for _ in 0..10_000 {
+            *seen.entry(row.sample(&mut random).unwrap()).or_insert(0) += 1;
+        }
+        assert_eq!(seen.len(), 10);
+        assert!(seen.keys().all(|a| fits(line, a)));
+        assert!(seen.values().all(|&n| (800..1200).contains(&n)));
+
+        // Far too many to list, but we can still pick one.
+        let mut row = SpringRow::new(line);
+        row.unfold(5);
+        let a = row.sample(&mut random).unwrap();
+        assert_eq!(groups(&a), [3, 2, 1].repeat(5));
+        assert_eq!(a.len(), 5 * 12 + 4);
+
+        // Too many even to count in a usize.
+        let mut row = SpringRow::new(line);
+        row.unfold(30);
+        let n = row.count::<BigUint>();
+        assert!(n > BigUint::from(usize::MAX));
+        let unfolded = format!(
+            "{} {}",
+            ["?###????????"; 30].join("?"),
+            ["3,2,1"; 30].join(",")
+        );
+        let picks: Vec<_> = [BigUint::zero(), &n / 3u32, &n - 1u32]
+            .into_iter()
+            .map(|k| row.sample(|_| k).unwrap())
+            .collect();
+        assert!(picks.iter().all(|a| fits(&unfolded, a)));
+        assert!(picks.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(row.sample(|n: BigUint| n), None);
+    }
+
+    #[test]
+    fn against_recursion() {
+        let mut rng = Lcg::new(13);
+        for _ in 0..200 {
+            let mut line = String::new();
+            for _ in 0..12 {
+                line.push(['.', '#', '?', '?'][rng.below(4)]);
+            }
+            line.push_str(" 2,1,3");
+            let mut row = SpringRow::new(&line);
+            assert_eq!(row.count::<usize>(), row.arrangements_recursive());
+            row.unfold(3);
+            assert_eq!(row.count::<usize>(), row.arrangements_recursive());
+        }
+        for line in SAMPLE.lines() {
+            let mut row = SpringRow::new(line);
+            row.unfold(5);
+            assert_eq!(row.count::<usize>(), row.arrangements_recursive());
+        }
+    }
+
+    #[test]
+    fn unfold_factor() {
+        let mut s = Springs::new(SAMPLE);
+        s.unfold(1);
+        assert_eq!(s.total_arrangements(), 21);
+        let mut s = Springs::new(SAMPLE);
+        s.unfold(5);
+        assert_eq!(s.total::<BigUint>(), BigUint::from(525152u32));
+
+        // Too many for a u64, but u128 can still check the big integers.
+        let mut s = Springs::new(SAMPLE);
+        s.unfold(18);
+        let total = s.total::<u128>();
+        assert!(total > u64::MAX as u128);
+        assert_eq!(s.total::<BigUint>(), BigUint::from(total));
+    }
+
+    #[test]
+    fn long_rows() {
+        // k groups of one in n springs is the same as choosing k of the
+        // n - k + 1 gaps between the operational springs.
+        let (n, k) = (5_000, 200);
+        let row = SpringRow::new(&format!("{} {}", "?".repeat(n), vec!["1"; k].join(",")));
+        let mut choose = BigUint::one();
+        for i in 0..k {
+            choose = choose * (n - k + 1 - i) / (i + 1);
+        }
+        assert_eq!(row.count::<BigUint>(), choose);
+    }
+
+    #[test]
+    fn nonograms() {
+        // A heart.
+        let heart: Nonogram = "1,1\n5\n5\n3\n1\n\n2\n4\n4\n4\n2".parse().unwrap();
+        let Solution::Unique(grid) = heart.solve() else {
+            panic!()
+        };
+        assert_eq!(grid.to_string(), ".#.#.\n#####\n#####\n.###.\n..#..\n");
+
+        // Either diagonal.
+        let diagonal: Nonogram = "1\n1\n\n1\n1".parse().unwrap();
+        assert!(matches!(diagonal.solve(), Solution::Multiple(..)));
+
+        let impossible: Nonogram = "1\n0\n\n0\n0".parse().unwrap();
+        assert_eq!(impossible.solve(), Solution::None);
+
+        assert!("1\n2".parse::<Nonogram>().is_err());
+        assert_eq!(
+            "1\n\n1\nx".parse::<Nonogram>().unwrap_err().to_string(),
+            "line 4: invalid digit found in string"
+        );
+
+        // More arrangements than a u128 holds, and every cell could go
+        // either way.
+        let blank = vec![Symbol::Unknown; 200];
+        assert_eq!(Nonogram::line(&blank, &[1; 60]), Some(blank.clone()));
+        let mut line = blank.clone();
+        line[0] = Symbol::Damaged;
+        line[1] = Symbol::Damaged;
+        assert_eq!(Nonogram::line(&line, &[1; 60]), None);
+    }
+
+    #[test]
+    fn random_nonograms() {
+        let clues = |lines: Vec<Vec<bool>>| -> String {
+            lines
+                .iter()
+                .map(|line| {
+                    let groups: Vec<_> = line
+                        .split(|&filled| !filled)
+                        .filter(|g| !g.is_empty())
+                        .map(|g| g.len().to_string())
+                        .collect();
+                    if groups.is_empty() {
+                        "0".to_owned()
+                    } else {
+                        groups.join(",")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let mut rng = Lcg::new(14);
+        for _ in 0..100 {
+            let picture: Vec<Vec<bool>> = (0..6)
+                .map(|_| (0..7).map(|_| rng.below(5) < 3).collect())
+                .collect();
+            let transposed = (0..7)
+                .map(|c| picture.iter().map(|row| row[c]).collect())
+                .collect();
+            let input = format!("{}\n\n{}", clues(picture.clone()), clues(transposed));
+            let nonogram: Nonogram = input.parse().unwrap();
+            let expected = Grid(
+                picture
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|&filled| {
+                                if filled {
+                                    Symbol::Damaged
+                                } else {
+                                    Symbol::Operational
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect(),
+            );
+            match nonogram.solve() {
+                Solution::Unique(grid) => assert_eq!(grid, expected),
+                Solution::Multiple(a, b) => {
+                    assert_ne!(a, b);
+                    // A finished grid gets through propagation only if every
+                    // line fits its clue.
+                    for grid in [a, b] {
+                        let mut solved = grid.clone();
+                        assert_eq!(nonogram.propagate(&mut solved), Some(()));
+                        assert_eq!(solved, grid);
+                    }
+                }
+                Solution::None => panic!("{input}"),
+            }
+        }
+    }
 }
@@ -3,7 +3,7 @@ use std::collections::{HashMap, VecDeque};
 use advent_of_code_2023::*;
 use petgraph::{
     Direction::Incoming,
-    graph::{DiGraph, NodeIndex},
+    graph::DiGraph,
     visit::{EdgeRef, NodeRef},
 };
 
@@ -42,18 +42,17 @@ fn main() {
 pub struct Puzzle {
     pub part1: Option<usize>,
     pub part2: Option<usize>,
-    graph: DiGraph<String, ()>,
-    modules: HashMap<String, Module>,
+    circuit: Circuit,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Pulse {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pulse {
     High,
     Low,
 }
 
 #[derive(Debug)]
-enum Module {
+pub enum Module {
     FlipFlop { on: bool },
     Conjunction { inputs: HashMap<String, Pulse> },
     Broadcast,
@@ -66,7 +65,7 @@ impl Module {
             Module::FlipFlop { on } => match pulse {
                 Pulse::High => None,
                 Pulse::Low => {
-                    *on = *on ^ true;
+                    *on = !*on;
                     if *on {
                         Some(Pulse::High)
                     } else {
@@ -97,8 +96,39 @@ impl Module {
     }
 }
 
-impl Solver for Puzzle {
-    fn new(input: &str) -> Self {
+/// How many high and low pulses a module has received and sent.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Counter {
+    pub high_in: usize,
+    pub low_in: usize,
+    pub high_out: usize,
+    pub low_out: usize,
+}
+
+/// A pulse in flight from one module to another.
+#[derive(Debug, Clone)]
+pub struct Signal {
+    pub pulse: Pulse,
+    pub src: String,
+    pub dst: String,
+}
+
+/// The module network and the queue of pulses still in flight. `solve` runs
+/// this as fast as it can, and the TUI steps through it one pulse at a time.
+#[derive(Default, Debug)]
+pub struct Circuit {
+    graph: DiGraph<String, ()>,
+    modules: HashMap<String, Module>,
+    outputs: HashMap<String, Vec<String>>,
+    queue: VecDeque<Signal>,
+    counters: HashMap<String, Counter>,
+    pub presses: usize,
+    pub highs: usize,
+    pub lows: usize,
+}
+
+impl Circuit {
+    pub fn new(input: &str) -> Self {
         let mut g = DiGraph::<String, ()>::new();
         let mut node_ids: HashMap<_, _> = input
             .lines()
@@ -127,7 +157,7 @@ impl Solver for Puzzle {
                 if !node_ids.contains_key(dst) {
                     assert!(dst == "rx" || dst == "output");
                     let node_id = g.add_node(dst.to_owned());
-                    node_ids.insert(&dst, node_id);
+                    node_ids.insert(dst, node_id);
                     modules.insert(dst.to_owned(), Module::ReceiveOnly);
                 }
                 // dbg!(&[src, dst]);
@@ -160,65 +190,166 @@ impl Solver for Puzzle {
             modules.insert(name.to_owned(), module);
         }
 
+        // Starting to really regret my choice of data structure here, so
+        // look up every module's outputs once instead of on every pulse.
+        // https://stackoverflow.com/a/77729229/5459668
+        let outputs = g
+            .node_indices()
+            .map(|id| {
+                // Petgraph lists edges newest first, but pulses go out in
+                // the order the input lists them.
+                let mut targets: Vec<String> = g
+                    .edges(id)
+                    .map(|edge| g[edge.target().id()].clone())
+                    .collect();
+                targets.reverse();
+                (g[id].clone(), targets)
+            })
+            .collect();
+
         Self {
-            part1: None,
-            part2: None,
             graph: g,
             modules,
+            outputs,
+            ..Default::default()
+        }
+    }
+
+    /// Push the button, queueing a low pulse for the broadcaster.
+    pub fn press(&mut self) {
+        self.presses += 1;
+        self.queue.push_back(Signal {
+            pulse: Pulse::Low,
+            src: "button".to_owned(),
+            dst: "broadcaster".to_owned(),
+        });
+    }
+
+    /// Deliver the next pulse in the queue, queueing whatever the receiving
+    /// module sends in response. Returns the pulse that was delivered.
+    pub fn step(&mut self) -> Option<Signal> {
+        let signal = self.queue.pop_front()?;
+        let pulse = signal.pulse;
+        match pulse {
+            Pulse::High => self.highs += 1,
+            Pulse::Low => self.lows += 1,
+        };
+        count(
+            self.counters.entry(signal.src.clone()).or_default(),
+            pulse,
+            false,
+        );
+        count(
+            self.counters.entry(signal.dst.clone()).or_default(),
+            pulse,
+            true,
+        );
+        let module = self.modules.get_mut(&signal.dst).unwrap();
+        if let Some(output) = module.send_receive(pulse, &signal.src) {
+            for next_dst in &self.outputs[&signal.dst] {
+                self.queue.push_back(Signal {
+                    pulse: output,
+                    src: signal.dst.clone(),
+                    dst: next_dst.clone(),
+                });
+            }
+        }
+        Some(signal)
+    }
+
+    /// The conjunction module feeding `rx` (`lx` in my input), if any.
+    pub fn rx_feeder(&self) -> Option<&str> {
+        let rx = self
+            .graph
+            .node_indices()
+            .find(|&id| self.graph[id] == "rx")?;
+        let edge = self.graph.edges_directed(rx, Incoming).next()?;
+        Some(&self.graph[edge.source()])
+    }
+}
+
+// These are only used by the TUI's circuit stepper.
+#[allow(dead_code)]
+impl Circuit {
+    /// Deliver every pulse still in flight.
+    pub fn settle(&mut self) {
+        while self.step().is_some() {}
+    }
+
+    pub fn queue(&self) -> &VecDeque<Signal> {
+        &self.queue
+    }
+
+    /// Modules in name order, with the broadcaster first.
+    pub fn modules(&self) -> Vec<(&str, &Module)> {
+        let mut modules: Vec<_> = self
+            .modules
+            .iter()
+            .map(|(name, module)| (name.as_str(), module))
+            .collect();
+        modules.sort_by_key(|&(name, _)| (name != "broadcaster", name));
+        modules
+    }
+
+    pub fn counter(&self, name: &str) -> Counter {
+        self.counters.get(name).copied().unwrap_or_default()
+    }
+}
+
+fn count(counter: &mut Counter, pulse: Pulse, incoming: bool) {
+    match (pulse, incoming) {
+        (Pulse::High, true) => counter.high_in += 1,
+        (Pulse::Low, true) => counter.low_in += 1,
+        (Pulse::High, false) => counter.high_out += 1,
+        (Pulse::Low, false) => counter.low_out += 1,
+    }
+}
+
+impl Solver for Puzzle {
+    fn new(input: &str) -> Self {
+        Self {
+            part1: None,
+            part2: None,
+            circuit: Circuit::new(input),
         }
     }
 
     fn solve(mut self) -> Self {
-        let mut highs = 0;
-        let mut lows = 0;
-        let mut queue: VecDeque<(Pulse, &str, &str)> = VecDeque::new();
         let mut part2: HashMap<String, usize> = HashMap::new();
+        let feeder = self.circuit.rx_feeder().map(str::to_owned);
 
+        let _phase = events::phase("button presses");
         'outer: for button_presses in 1..10000 {
-            queue.push_back((Pulse::Low, "button", "broadcaster"));
-            let mut dh = 0;
-            let mut dl = 0;
+            if button_presses % 1000 == 0 {
+                events::progress("button presses", button_presses, 10000);
+            }
+            self.circuit.press();
 
-            while let Some((pulse, src, dst)) = queue.pop_front() {
-                if dst == "rx"
-                    && let Module::Conjunction { inputs } = &self.modules["lx"]
+            while let Some(signal) = self.circuit.step() {
+                if signal.dst == "rx"
+                    && let Some(feeder) = &feeder
+                    && let Module::Conjunction { inputs } = &self.circuit.modules[feeder]
                 {
                     for (key, value) in inputs.iter() {
                         if matches!(value, Pulse::High) && !part2.contains_key(key) {
                             part2.insert(key.to_owned(), button_presses);
                         }
                     }
-                    if part2.len() == 4 {
+                    if part2.len() == inputs.len() {
                         // println!("{part2:?}");
+                        for (key, value) in &part2 {
+                            events::counter(&format!("first high from {key}"), *value);
+                        }
                         self.part2 = Some(part2.values().product());
                         break 'outer;
                     }
                 }
-                match pulse {
-                    Pulse::High => dh += 1,
-                    Pulse::Low => dl += 1,
-                };
-                if let Some(output) = self.modules.get_mut(dst).unwrap().send_receive(pulse, src) {
-                    // Starting to really regret my choice of data structure here.
-                    // https://stackoverflow.com/a/77729229/5459668
-                    let src_id = NodeIndex::new(
-                        self.graph
-                            .raw_nodes()
-                            .iter()
-                            .position(|n| n.weight == dst)
-                            .unwrap(),
-                    );
-                    for edge in self.graph.edges(src_id) {
-                        let next_dst = &self.graph[edge.target().id()];
-                        queue.push_back((output, dst, next_dst));
-                    }
-                }
             }
 
-            highs += dh;
-            lows += dl;
             if button_presses == 1000 {
-                self.part1 = Some(highs * lows);
+                events::counter("high pulses", self.circuit.highs);
+                events::counter("low pulses", self.circuit.lows);
+                self.part1 = Some(self.circuit.highs * self.circuit.lows);
             }
         }
         self
@@ -241,4 +372,24 @@ mod pulse_propagation {
     fn test2() {
         assert_eq!(Puzzle::new(SAMPLE2).solve().part1, Some(11687500));
     }
+
+    #[test]
+    fn stepping() {
+        // "In the first example, the same thing happens every time the button
+        // is pushed: 8 low pulses and 4 high pulses are sent."
+        let mut circuit = Circuit::new(SAMPLE1);
+        circuit.press();
+        let first = circuit.step().unwrap();
+        assert_eq!((first.pulse, first.src.as_str()), (Pulse::Low, "button"));
+        assert_eq!(circuit.queue().len(), 3);
+        circuit.settle();
+        assert_eq!((circuit.highs, circuit.lows), (4, 8));
+        assert_eq!(circuit.counter("inv").high_in, 1);
+        assert_eq!(circuit.counter("inv").low_out, 1);
+        assert!(matches!(
+            circuit.modules()[0],
+            ("broadcaster", Module::Broadcast)
+        ));
+        assert_eq!(circuit.rx_feeder(), None);
+    }
 }
@@ -190,18 +190,21 @@ impl Puzzle {
         self.bricks.iter().map(|brick| brick.start.z).max().unwrap()
     }
 
-    #[allow(dead_code)]
     fn tally(&self) {
-        println!(
-            "{:?} ({} total)",
+        if !events::enabled() {
+            return;
+        }
+        let volumes =
             self.bricks
                 .iter()
                 .fold(BTreeMap::new(), |mut acc: BTreeMap<usize, usize>, brick| {
                     *acc.entry(brick.volume()).or_default() += 1;
                     acc
-                }),
-            self.bricks.len()
-        );
+                });
+        for (volume, count) in volumes {
+            events::counter(&format!("bricks of volume {volume}"), count);
+        }
+        events::counter("bricks", self.bricks.len());
     }
 }
 
@@ -217,6 +220,7 @@ impl Solver for Puzzle {
     }
 
     fn solve(mut self) -> Self {
+        self.tally();
         self.fall_top_down();
         // let (part1, part2) = self.disintegratable_bricks();
         // self.part1 = Some(part1.len());
@@ -322,7 +322,12 @@ impl Solver for Puzzle {
             max_y = 400000000000000.0;
         }
 
+        let _phase = events::phase("pairwise intersections");
         let mut part1 = 0;
+        // Crossed in the past for A, for B, and for both.
+        let mut past = [0; 3];
+        let mut outside = 0;
+        let mut parallel = 0;
         let n = self.hailstones.len();
         for (i, j) in (0..n).cartesian_product(0..n) {
             if i >= j {
@@ -332,73 +337,38 @@ impl Solver for Puzzle {
             let p2 = &self.hailstones[j];
 
             if p1.position == p2.position {
-                println!("Same starting position:");
-                println!("- {p1}");
-                println!("- {p2}");
+                events::warning(&format!("Same starting position: {p1} and {p2}"));
             }
 
             if p1.velocity == p2.velocity {
-                println!("Same velocity:");
-                println!("- {p1}");
-                println!("- {p2}");
+                events::warning(&format!("Same velocity: {p1} and {p2}"));
             }
 
-            #[cfg(test)]
+            let Some((intersection, t)) = p1.intersection_without_z(p2) else {
+                parallel += 1;
+                continue;
+            };
+            if !(min_x <= intersection[0]
+                && intersection[0] <= max_x
+                && min_y <= intersection[1]
+                && intersection[1] <= max_y)
             {
-                println!("Hailstone A: {p1}");
-                println!("Hailstone B: {p2}");
-            }
-            if let Some((intersection, t)) = p1.intersection_without_z(p2) {
-                if min_x <= intersection[0]
-                    && intersection[0] <= max_x
-                    && min_y <= intersection[1]
-                    && intersection[1] <= max_y
-                {
-                    if t[0].is_sign_negative() && t[1].is_sign_negative() {
-                        #[cfg(test)]
-                        {
-                            println!("Hailstones' paths crossed in the past for both hailstones.");
-                        }
-                    } else if t[0].is_sign_negative() {
-                        #[cfg(test)]
-                        {
-                            println!("Hailstones' paths crossed in the past for hailstone A.");
-                        }
-                    } else if t[1].is_sign_negative() {
-                        #[cfg(test)]
-                        {
-                            println!("Hailstones' paths crossed in the past for hailstone B.");
-                        }
-                    } else {
-                        #[cfg(test)]
-                        {
-                            println!(
-                                "Hailstones' paths will cross inside the test area (at x={}, y={}).",
-                                intersection[0], intersection[1]
-                            );
-                        }
-                        part1 += 1;
-                    }
-                } else {
-                    #[cfg(test)]
-                    {
-                        println!(
-                            "Hailstones' paths will cross outside the test area (at x={}, y={}).",
-                            intersection[0], intersection[1]
-                        );
-                    }
-                }
-            } else {
-                #[cfg(test)]
-                {
-                    println!("Hailstone's paths are parallel; they never intersect.");
-                }
+                outside += 1;
+                continue;
             }
-            #[cfg(test)]
-            {
-                println!();
+            match (t[0].is_sign_negative(), t[1].is_sign_negative()) {
+                (true, false) => past[0] += 1,
+                (false, true) => past[1] += 1,
+                (true, true) => past[2] += 1,
+                (false, false) => part1 += 1,
             }
         }
+        events::counter("crossed in the past", past.iter().sum::<usize>());
+        events::counter("crossed in the past for A only", past[0]);
+        events::counter("crossed in the past for B only", past[1]);
+        events::counter("crossed in the past for both", past[2]);
+        events::counter("crossed outside the test area", outside);
+        events::counter("parallel", parallel);
         self.part1 = Some(part1);
 
         let (a, b, c) = tockman(&self.hailstones);
@@ -0,0 +1,191 @@
+//! Progress and diagnostics from inside the solvers, for when `println!` isn't
+//! good enough. Solvers call the free functions here; nothing happens unless
+//! the runner has installed an [`Observer`] with [`set_observer`], so the
+//! calls are cheap enough to leave in. Hot loops should still only report
+//! every so often.
+//!
+//! [`JsonLines`] writes each event as one line of JSON, which is what
+//! `tui --headless` sends to stderr.
+
+use std::{
+    io::Write,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::report::json_string;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    PhaseStarted { phase: &'a str },
+    PhaseFinished { phase: &'a str, elapsed: Duration },
+    Counter { name: &'a str, value: u64 },
+    Progress { phase: &'a str, done: u64, total: u64 },
+    Warning { message: &'a str },
+}
+
+pub trait Observer: Send + Sync {
+    fn event(&self, event: &Event);
+}
+
+static OBSERVER: OnceLock<Box<dyn Observer>> = OnceLock::new();
+
+/// Install the observer for the rest of the program. There can only be one.
+pub fn set_observer(observer: impl Observer + 'static) -> Result<(), &'static str> {
+    OBSERVER
+        .set(Box::new(observer))
+        .map_err(|_| "an observer is already installed")
+}
+
+/// Is anybody listening? Check this before formatting an expensive message.
+pub fn enabled() -> bool {
+    OBSERVER.get().is_some()
+}
+
+pub fn emit(event: Event) {
+    if let Some(observer) = OBSERVER.get() {
+        observer.event(&event);
+    }
+}
+
+/// Start a phase. It finishes when the returned guard is dropped.
+#[must_use = "the phase finishes as soon as the guard is dropped"]
+pub fn phase(phase: impl Into<String>) -> Phase {
+    let phase = phase.into();
+    emit(Event::PhaseStarted { phase: &phase });
+    Phase {
+        phase,
+        start: Instant::now(),
+    }
+}
+
+pub fn counter(name: &str, value: impl TryInto<u64>) {
+    if enabled() {
+        let value = value.try_into().unwrap_or(u64::MAX);
+        emit(Event::Counter { name, value });
+    }
+}
+
+pub fn progress(phase: &str, done: impl TryInto<u64>, total: impl TryInto<u64>) {
+    if enabled() {
+        let done = done.try_into().unwrap_or(u64::MAX);
+        let total = total.try_into().unwrap_or(u64::MAX);
+        emit(Event::Progress { phase, done, total });
+    }
+}
+
+pub fn warning(message: &str) {
+    emit(Event::Warning { message });
+}
+
+pub struct Phase {
+    phase: String,
+    start: Instant,
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        emit(Event::PhaseFinished {
+            phase: &self.phase,
+            elapsed: self.start.elapsed(),
+        });
+    }
+}
+
+/// Writes every event as a line of JSON, stamped with the seconds since the
+/// observer was created.
+pub struct JsonLines<W> {
+    writer: Mutex<W>,
+    start: Instant,
+}
+
+impl<W: Write + Send> JsonLines<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl<W: Write + Send> Observer for JsonLines<W> {
+    fn event(&self, event: &Event) {
+        let line = json(event, self.start.elapsed());
+        if let Ok(mut writer) = self.writer.lock() {
+            // There's nobody to tell if stderr is gone.
+            let _ = writeln!(writer, "{line}");
+        }
+    }
+}
+
+fn json(event: &Event, t: Duration) -> String {
+    let t = t.as_secs_f64();
+    match event {
+        Event::PhaseStarted { phase } => format!(
+            "{{\"t\": {t:.6}, \"event\": \"phase_started\", \"phase\": {}}}",
+            json_string(phase)
+        ),
+        Event::PhaseFinished { phase, elapsed } => format!(
+            "{{\"t\": {t:.6}, \"event\": \"phase_finished\", \"phase\": {}, \"elapsed_ns\": {}}}",
+            json_string(phase),
+            elapsed.as_nanos()
+        ),
+        Event::Counter { name, value } => format!(
+            "{{\"t\": {t:.6}, \"event\": \"counter\", \"name\": {}, \"value\": {value}}}",
+            json_string(name)
+        ),
+        Event::Progress { phase, done, total } => format!(
+            "{{\"t\": {t:.6}, \"event\": \"progress\", \"phase\": {}, \"done\": {done}, \"total\": {total}, \"percent\": {:.1}}}",
+            json_string(phase),
+            if *total == 0 {
+                100.0
+            } else {
+                100.0 * *done as f64 / *total as f64
+            }
+        ),
+        Event::Warning { message } => format!(
+            "{{\"t\": {t:.6}, \"event\": \"warning\", \"message\": {}}}",
+            json_string(message)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_lines() {
+        let observer = JsonLines::new(Vec::new());
+        observer.event(&Event::PhaseStarted { phase: "day 5" });
+        observer.event(&Event::Progress {
+            phase: "part 2",
+            done: 25,
+            total: 100,
+        });
+        observer.event(&Event::Warning {
+            message: "same \"velocity\"",
+        });
+        let output = String::from_utf8(observer.writer.into_inner().unwrap()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("\"event\": \"phase_started\", \"phase\": \"day 5\"}"));
+        assert!(lines[1].ends_with("\"done\": 25, \"total\": 100, \"percent\": 25.0}"));
+        assert!(lines[2].ends_with("\"message\": \"same \\\"velocity\\\"\"}"));
+    }
+
+    #[test]
+    fn finished() {
+        let line = json(
+            &Event::PhaseFinished {
+                phase: "part 1",
+                elapsed: Duration::from_micros(3),
+            },
+            Duration::from_millis(1500),
+        );
+        assert_eq!(
+            line,
+            "{\"t\": 1.500000, \"event\": \"phase_finished\", \"phase\": \"part 1\", \"elapsed_ns\": 3000}"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Key bindings for the TUI. The defaults use the arrow keys, and a bindings
+//! file can add or override keys using the same notation as our Emacs-style
+//! keybindings notes: one `<key> <action>` per line, where a key is a single
+//! character or a name like `tab` or `pagedown`, optionally prefixed with
+//! `C-` (control) or `M-` (meta/alt). Lines starting with `#` are comments.
+//!
+//! ```text
+//! C-f   right
+//! C-b   left
+//! M-v   page-up
+//! ```
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{collections::HashMap, error::Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    NextInput,
+    PreviousInput,
+    Solve,
+    Export,
+    ToggleList,
+    Inspect,
+    Step,
+    Press,
+    Back,
+}
+
+impl Action {
+    const ALL: [(&str, Action); 18] = [
+        ("quit", Action::Quit),
+        ("left", Action::Left),
+        ("right", Action::Right),
+        ("up", Action::Up),
+        ("down", Action::Down),
+        ("page-up", Action::PageUp),
+        ("page-down", Action::PageDown),
+        ("first", Action::First),
+        ("last", Action::Last),
+        ("next-input", Action::NextInput),
+        ("previous-input", Action::PreviousInput),
+        ("solve", Action::Solve),
+        ("export", Action::Export),
+        ("toggle-list", Action::ToggleList),
+        ("inspect", Action::Inspect),
+        ("step", Action::Step),
+        ("press", Action::Press),
+        ("back", Action::Back),
+    ];
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, action)| *action)
+    }
+}
+
+type Key = (KeyCode, KeyModifiers);
+
+#[derive(Debug)]
+pub struct KeyMap(HashMap<Key, Action>);
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut map = Self(HashMap::new());
+        for (key, action) in [
+            ("q", "quit"),
+            ("left", "left"),
+            ("right", "right"),
+            ("up", "up"),
+            ("down", "down"),
+            ("pageup", "page-up"),
+            ("pagedown", "page-down"),
+            ("home", "first"),
+            ("end", "last"),
+            ("tab", "next-input"),
+            ("backtab", "previous-input"),
+            ("enter", "solve"),
+            ("e", "export"),
+            ("l", "toggle-list"),
+            ("i", "inspect"),
+            ("s", "step"),
+            ("space", "step"),
+            ("p", "press"),
+            ("esc", "back"),
+        ] {
+            map.bind(key, action).unwrap();
+        }
+        map
+    }
+}
+
+impl KeyMap {
+    /// The default bindings, with every line of `config` added on top.
+    pub fn new(config: &str) -> Result<Self, Box<dyn Error>> {
+        let mut map = Self::default();
+        for (i, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_ascii_whitespace().collect::<Vec<_>>()[..] {
+                [key, action] => map
+                    .bind(key, action)
+                    .map_err(|e| format!("line {}: {e}", i + 1))?,
+                _ => Err(format!("line {}: expected `<key> <action>`", i + 1))?,
+            }
+        }
+        Ok(map)
+    }
+
+    fn bind(&mut self, key: &str, action: &str) -> Result<(), Box<dyn Error>> {
+        let key = parse_key(key)?;
+        let action = Action::parse(action).ok_or(format!("unknown action {action}"))?;
+        self.0.insert(key, action);
+        Ok(())
+    }
+
+    pub fn get(&self, event: KeyEvent) -> Option<Action> {
+        self.0.get(&normalize(event.code, event.modifiers)).copied()
+    }
+
+    /// Every key bound to any of these actions, like `s/space`. `None` if
+    /// nothing is bound to them.
+    pub fn describe(&self, actions: &[Action]) -> Option<String> {
+        let mut keys = vec![];
+        for action in actions {
+            let mut names: Vec<String> = self
+                .0
+                .iter()
+                .filter(|(_, a)| *a == action)
+                .map(|(key, _)| key_name(key))
+                .collect();
+            names.sort();
+            keys.extend(names);
+        }
+        (!keys.is_empty()).then(|| keys.join("/"))
+    }
+
+    /// A help line like `q: quit · e: export`, for whatever keys are bound
+    /// right now. Actions with no keys are left out.
+    pub fn help(&self, items: &[(&[Action], &str)]) -> String {
+        items
+            .iter()
+            .filter_map(|(actions, label)| Some(format!("{}: {label}", self.describe(actions)?)))
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+}
+
+/// How to show a key in the help, in the same notation as the bindings file
+/// except for the arrows.
+fn key_name((code, modifiers): &Key) -> String {
+    let mut name = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        name.push_str("C-");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        name.push_str("M-");
+    }
+    match code {
+        KeyCode::Left => name.push('←'),
+        KeyCode::Right => name.push('→'),
+        KeyCode::Up => name.push('↑'),
+        KeyCode::Down => name.push('↓'),
+        KeyCode::PageUp => name.push_str("pageup"),
+        KeyCode::PageDown => name.push_str("pagedown"),
+        KeyCode::Home => name.push_str("home"),
+        KeyCode::End => name.push_str("end"),
+        KeyCode::Tab => name.push_str("tab"),
+        KeyCode::BackTab => name.push_str("backtab"),
+        KeyCode::Enter => name.push_str("enter"),
+        KeyCode::Esc => name.push_str("esc"),
+        KeyCode::Char(' ') => name.push_str("space"),
+        KeyCode::Char(c) => name.push(*c),
+        code => name.push_str(&format!("{code:?}").to_lowercase()),
+    }
+    name
+}
+
+/// Terminals disagree about whether shifted characters carry the shift
+/// modifier, so we ignore it and let the character speak for itself.
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> Key {
+    (
+        code,
+        modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+    )
+}
+
+fn parse_key(key: &str) -> Result<Key, Box<dyn Error>> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = key;
+    loop {
+        if let Some(r) = rest.strip_prefix("C-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("M-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = r;
+        } else {
+            break;
+        }
+    }
+    let code = match rest.to_ascii_lowercase().as_str() {
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "enter" | "ret" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "space" | "spc" => KeyCode::Char(' '),
+        _ => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => Err(format!("unknown key {key}"))?,
+            }
+        }
+    };
+    Ok(normalize(code, modifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn defaults() {
+        let map = KeyMap::default();
+        assert_eq!(
+            map.get(press(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            map.get(press(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            Some(Action::PreviousInput)
+        );
+    }
+
+    #[test]
+    fn emacs() {
+        let map = KeyMap::new("# Emacs\nC-f right\nM-v   page-up\nC-M-x quit").unwrap();
+        assert_eq!(
+            map.get(press(KeyCode::Char('f'), KeyModifiers::CONTROL)),
+            Some(Action::Right)
+        );
+        assert_eq!(
+            map.get(press(KeyCode::Char('v'), KeyModifiers::ALT)),
+            Some(Action::PageUp)
+        );
+        assert_eq!(
+            map.get(press(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )),
+            Some(Action::Quit)
+        );
+        assert_eq!(map.get(press(KeyCode::Char('f'), KeyModifiers::NONE)), None);
+        // The defaults are still there.
+        assert_eq!(
+            map.get(press(KeyCode::Right, KeyModifiers::NONE)),
+            Some(Action::Right)
+        );
+    }
+
+    #[test]
+    fn help() {
+        let items: &[(&[Action], &str)] = &[
+            (&[Action::Step], "step"),
+            (&[Action::Up, Action::Down], "scroll"),
+            (&[Action::Inspect], "inspect"),
+        ];
+        assert_eq!(
+            KeyMap::default().help(items),
+            "s/space: step · ↑/↓: scroll · i: inspect"
+        );
+        let map = KeyMap::new("C-s step\nC-p up\nM-i inspect").unwrap();
+        assert_eq!(
+            map.help(items),
+            "C-s/s/space: step · C-p/↑/↓: scroll · M-i/i: inspect"
+        );
+        assert_eq!(map.describe(&[]), None);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(KeyMap::new("C-f").is_err());
+        assert!(KeyMap::new("C-f fly").is_err());
+        assert!(KeyMap::new("foo right").is_err());
+    }
+}
@@ -1,3 +1,9 @@
+pub mod events;
+pub mod keys;
+pub mod report;
+pub mod rng;
+pub mod stream;
+
 use ndarray::Array2;
 use std::error::Error;
 
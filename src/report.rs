@@ -0,0 +1,350 @@
+//! Dashboard results as Markdown, JSON, or a standalone HTML page, so we can
+//! publish them without taking screenshots of the TUI.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+pub const TITLES: [&str; 25] = [
+    "Trebuchet?!",
+    "Cube Conundrum",
+    "Gear Ratios",
+    "Scratchcards",
+    "If You Give A Seed A Fertilizer",
+    "Wait For It",
+    "Camel Cards",
+    "Haunted Wasteland",
+    "Mirage Maintenance",
+    "Pipe Maze",
+    "Cosmic Expansion",
+    "Hot Springs",
+    "Point of Incidence",
+    "Parabolic Reflector Dish",
+    "Lens Library",
+    "The Floor Will Be Lava",
+    "Clumsy Crucible",
+    "Lavaduct Lagoon",
+    "Aplenty",
+    "Pulse Propagation",
+    "Step Counter",
+    "Sand Slabs",
+    "A Long Walk",
+    "Never Tell Me The Odds",
+    "Snowverload",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Correct,
+    Wrong,
+    Unknown,
+    Panicked,
+}
+
+impl Status {
+    /// Compare an answer against the expected value, if we have one.
+    pub fn check(actual: Option<usize>, expected: Option<usize>) -> Self {
+        match expected {
+            Some(e) if actual == Some(e) => Status::Correct,
+            Some(_) => Status::Wrong,
+            None => Status::Unknown,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Correct => "correct",
+            Status::Wrong => "wrong",
+            Status::Unknown => "unknown",
+            Status::Panicked => "panicked",
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Status::Correct => "✓",
+            Status::Wrong => "✗",
+            Status::Unknown => "?",
+            Status::Panicked => "💥",
+        }
+    }
+
+    /// The status of a whole day is its worst part.
+    fn combine(self, other: Self) -> Self {
+        use Status::*;
+        match (self, other) {
+            (Panicked, _) | (_, Panicked) => Panicked,
+            (Wrong, _) | (_, Wrong) => Wrong,
+            (Unknown, _) | (_, Unknown) => Unknown,
+            (Correct, Correct) => Correct,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub day: usize,
+    pub input: String,
+    pub part1: Option<usize>,
+    pub part2: Option<usize>,
+    pub runtime: Option<Duration>,
+    pub status: [Status; 2],
+}
+
+impl Row {
+    pub fn title(&self) -> &'static str {
+        TITLES[self.day - 1]
+    }
+
+    pub fn status(&self) -> Status {
+        self.status[0].combine(self.status[1])
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub rows: Vec<Row>,
+    pub features: Vec<&'static str>,
+    pub profile: &'static str,
+}
+
+impl Report {
+    pub fn new(rows: Vec<Row>) -> Self {
+        let mut features = vec![];
+        if cfg!(feature = "faster") {
+            features.push("faster");
+        }
+        let profile = if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        };
+        Self {
+            rows,
+            features,
+            profile,
+        }
+    }
+
+    fn features(&self) -> String {
+        if self.features.is_empty() {
+            "none".to_owned()
+        } else {
+            self.features.join(", ")
+        }
+    }
+
+    /// A table for the README.
+    pub fn markdown(&self) -> String {
+        let mut s = String::new();
+        writeln!(
+            s,
+            "| Day | Title | Input | Part 1 | Part 2 | Runtime | Status |"
+        )
+        .unwrap();
+        writeln!(
+            s,
+            "|----:|-------|-------|-------:|-------:|--------:|:------:|"
+        )
+        .unwrap();
+        for row in &self.rows {
+            writeln!(
+                s,
+                "| {} | {} | `{}` | {} | {} | {} | {} |",
+                row.day,
+                row.title(),
+                row.input,
+                answer(row.part1),
+                answer(row.part2),
+                runtime(row.runtime),
+                row.status().symbol()
+            )
+            .unwrap();
+        }
+        writeln!(s).unwrap();
+        writeln!(
+            s,
+            "Built with the `{}` profile. Features: {}.",
+            self.profile,
+            self.features()
+        )
+        .unwrap();
+        s
+    }
+
+    /// Everything, for other tools. Runtimes are in nanoseconds.
+    pub fn json(&self) -> String {
+        let mut s = String::new();
+        let features = self
+            .features
+            .iter()
+            .map(|f| json_string(f))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(s, "{{").unwrap();
+        writeln!(s, "  \"profile\": {},", json_string(self.profile)).unwrap();
+        writeln!(s, "  \"features\": [{features}],").unwrap();
+        writeln!(s, "  \"days\": [").unwrap();
+        for (i, row) in self.rows.iter().enumerate() {
+            let comma = if i + 1 < self.rows.len() { "," } else { "" };
+            writeln!(
+                s,
+                "    {{\"day\": {}, \"title\": {}, \"input\": {}, \"part1\": {}, \"part2\": {}, \"runtime_ns\": {}, \"status\": [{}, {}]}}{comma}",
+                row.day,
+                json_string(row.title()),
+                json_string(&row.input),
+                json_number(row.part1),
+                json_number(row.part2),
+                json_number(row.runtime.map(|r| r.as_nanos())),
+                json_string(row.status[0].name()),
+                json_string(row.status[1].name()),
+            )
+            .unwrap();
+        }
+        writeln!(s, "  ]").unwrap();
+        writeln!(s, "}}").unwrap();
+        s
+    }
+
+    /// A self-contained page with inline styles and no scripts.
+    pub fn html(&self) -> String {
+        let mut s = String::new();
+        writeln!(
+            s,
+            "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>Advent of Code 2023</title>
+<style>
+body {{ background: #0f0f23; color: #cccccc; font-family: monospace; }}
+h1 {{ color: #ffff66; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #333340; padding: 0.2em 0.6em; }}
+td.number {{ text-align: right; }}
+.correct {{ color: #00cc00; }}
+.wrong, .panicked {{ color: #ff4444; }}
+.unknown {{ color: #888888; }}
+</style>
+</head>
+<body>
+<h1>Advent of Code 2023</h1>
+<table>
+<tr><th>Day</th><th>Title</th><th>Input</th><th>Part 1</th><th>Part 2</th><th>Runtime</th><th>Status</th></tr>"
+        )
+        .unwrap();
+        for row in &self.rows {
+            let status = row.status();
+            writeln!(
+                s,
+                "<tr><td class=\"number\">{}</td><td>{}</td><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td><td class=\"{}\">{}</td></tr>",
+                row.day,
+                html_escape(row.title()),
+                html_escape(&row.input),
+                answer(row.part1),
+                answer(row.part2),
+                runtime(row.runtime),
+                status.name(),
+                status.symbol()
+            )
+            .unwrap();
+        }
+        writeln!(
+            s,
+            "</table>
+<p>Built with the <code>{}</code> profile. Features: {}.</p>
+<p><a href=\"https://github.com/wjholden/Advent-of-Code-2023\">https://github.com/wjholden/Advent-of-Code-2023</a></p>
+</body>
+</html>",
+            self.profile,
+            html_escape(&self.features())
+        )
+        .unwrap();
+        s
+    }
+}
+
+fn answer(value: Option<usize>) -> String {
+    value.map_or("-".to_owned(), |v| v.to_string())
+}
+
+fn runtime(value: Option<Duration>) -> String {
+    value.map_or("-".to_owned(), |r| format!("{r:?}"))
+}
+
+fn json_number<T: ToString>(value: Option<T>) -> String {
+    value.map_or("null".to_owned(), |v| v.to_string())
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut s = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(s, "\\u{:04x}", c as u32).unwrap(),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Report {
+        Report::new(vec![
+            Row {
+                day: 1,
+                input: "day01.txt".to_owned(),
+                part1: Some(142),
+                part2: Some(281),
+                runtime: Some(Duration::from_micros(5)),
+                status: [Status::Correct, Status::Unknown],
+            },
+            Row {
+                day: 20,
+                input: "samples/day20-1.txt".to_owned(),
+                part1: Some(32000000),
+                part2: None,
+                runtime: None,
+                status: [Status::Panicked, Status::Panicked],
+            },
+        ])
+    }
+
+    #[test]
+    fn markdown() {
+        let md = sample().markdown();
+        assert!(md.contains("| 1 | Trebuchet?! | `day01.txt` | 142 | 281 | 5µs | ? |"));
+        assert!(md.contains(
+            "| 20 | Pulse Propagation | `samples/day20-1.txt` | 32000000 | - | - | 💥 |"
+        ));
+    }
+
+    #[test]
+    fn json() {
+        let json = sample().json();
+        assert!(json.contains("\"part2\": null, \"runtime_ns\": null"));
+        assert!(json.contains("\"status\": [\"correct\", \"unknown\"]"));
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+    }
+
+    #[test]
+    fn html() {
+        let html = sample().html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td class=\"panicked\">💥</td>"));
+        assert_eq!(html_escape("<a & b>"), "&lt;a &amp; b&gt;");
+    }
+}
@@ -0,0 +1,30 @@
+//! A tiny seeded generator for the randomised tests that check the fast
+//! solvers against brute force. The binaries' tests link against the normal
+//! build of this library, so this can't hide behind `#[cfg(test)]`.
+
+/// Knuth's MMIX linear congruential generator. Plenty for making up puzzle
+/// inputs, and the same seed always gives the same cases.
+#[derive(Debug, Clone)]
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg(seed)
+    }
+
+    /// The next 31 random bits, so the top bit is always clear. The low bits
+    /// of an LCG are poor, so we keep only the high ones.
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as u32
+    }
+
+    /// Something in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "nothing is below 0");
+        self.next_u32() as usize % n
+    }
+}
@@ -0,0 +1,140 @@
+//! Line-by-line processing of inputs too big to hold in memory, for pushing
+//! synthetic multi-gigabyte files through the parsers. Only one line (or, in
+//! parallel, two chunks of lines) is held at a time.
+
+use rayon::prelude::*;
+use std::{
+    fmt,
+    io::{self, BufRead},
+    time::{Duration, Instant},
+};
+
+/// How much input we got through, and how fast.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Throughput {
+    pub bytes: u64,
+    pub lines: u64,
+    pub elapsed: Duration,
+}
+
+impl Throughput {
+    pub fn mb_per_sec(&self) -> f64 {
+        self.bytes as f64 / 1e6 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} lines, {:.1} MB in {:?} ({:.1} MB/s)",
+            self.lines,
+            self.bytes as f64 / 1e6,
+            self.elapsed,
+            self.mb_per_sec()
+        )
+    }
+}
+
+/// Feed every line through `map` and then `fold`, in order, reusing a single
+/// line buffer.
+pub fn for_each_line<R: BufRead, T>(
+    mut reader: R,
+    map: impl Fn(&str) -> T,
+    mut fold: impl FnMut(T),
+) -> io::Result<Throughput> {
+    let start = Instant::now();
+    let mut throughput = Throughput::default();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let n = reader.read_line(&mut line)?;
+        if n == 0 {
+            break;
+        }
+        throughput.bytes += n as u64;
+        throughput.lines += 1;
+        fold(map(line.trim_end_matches(['\n', '\r'])));
+    }
+    throughput.elapsed = start.elapsed();
+    Ok(throughput)
+}
+
+/// Like [`for_each_line`], but `map` runs in parallel over chunks of
+/// `chunk_lines` lines while the next chunk is being read. `fold` still sees
+/// the results in input order, so it can carry state from line to line.
+pub fn par_for_each_line<R: BufRead + Send, T: Send>(
+    mut reader: R,
+    chunk_lines: usize,
+    map: impl Fn(&str) -> T + Sync,
+    mut fold: impl FnMut(T),
+) -> io::Result<Throughput> {
+    let start = Instant::now();
+    let mut throughput = Throughput::default();
+    let mut chunk = read_chunk(&mut reader, chunk_lines, &mut throughput)?;
+    while !chunk.is_empty() {
+        let mut next_throughput = throughput;
+        let (mapped, next) = rayon::join(
+            || chunk.par_iter().map(|line| map(line)).collect::<Vec<_>>(),
+            || read_chunk(&mut reader, chunk_lines, &mut next_throughput),
+        );
+        mapped.into_iter().for_each(&mut fold);
+        throughput = next_throughput;
+        chunk = next?;
+    }
+    throughput.elapsed = start.elapsed();
+    Ok(throughput)
+}
+
+fn read_chunk<R: BufRead>(
+    reader: &mut R,
+    chunk_lines: usize,
+    throughput: &mut Throughput,
+) -> io::Result<Vec<String>> {
+    let mut chunk = Vec::with_capacity(chunk_lines);
+    for _ in 0..chunk_lines.max(1) {
+        let mut line = String::new();
+        let n = reader.read_line(&mut line)?;
+        if n == 0 {
+            break;
+        }
+        throughput.bytes += n as u64;
+        throughput.lines += 1;
+        line.truncate(line.trim_end_matches(['\n', '\r']).len());
+        chunk.push(line);
+    }
+    Ok(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "3\r\n1\n4\n1\n5\n9\n2\n6";
+
+    #[test]
+    fn sequential() {
+        let mut lines = vec![];
+        let throughput = for_each_line(INPUT.as_bytes(), str::to_owned, |l| lines.push(l)).unwrap();
+        assert_eq!(lines, ["3", "1", "4", "1", "5", "9", "2", "6"]);
+        assert_eq!(throughput.lines, 8);
+        assert_eq!(throughput.bytes, INPUT.len() as u64);
+    }
+
+    #[test]
+    fn parallel_keeps_order() {
+        for chunk_lines in [1, 3, 8, 100] {
+            let mut digits = String::new();
+            let throughput = par_for_each_line(
+                INPUT.as_bytes(),
+                chunk_lines,
+                |line| line.parse::<u32>().unwrap(),
+                |d| digits.push_str(&d.to_string()),
+            )
+            .unwrap();
+            assert_eq!(digits, "31415926");
+            assert_eq!(throughput.lines, 8);
+            assert_eq!(throughput.bytes, INPUT.len() as u64);
+        }
+    }
+}